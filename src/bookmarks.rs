@@ -0,0 +1,49 @@
+//! Bookmark queries beyond "what points at @"
+
+use jj_lib::backend::CommitId;
+use jj_lib::git;
+use jj_lib::repo::Repo;
+
+/// Bookmark names the default `trunk()` alias looks for
+const TRUNK_NAMES: &[&str] = &["main", "master", "trunk"];
+
+/// Move trunk-like bookmarks to the end, keeping the order otherwise, so a
+/// shortened list shows the feature bookmarks first
+pub fn sort_trunk_last(bookmarks: &mut [String]) {
+    bookmarks.sort_by_key(|name| TRUNK_NAMES.contains(&name.as_str()));
+}
+
+/// Count local bookmarks that are safe to delete: everything they point at is
+/// already on their tracked remote bookmark and merged into `trunk`
+pub fn count_stale(repo: &dyn Repo, trunk: &CommitId) -> usize {
+    let index = repo.index();
+    let is_ancestor = |ancestor: &CommitId, descendant: &CommitId| {
+        index.is_ancestor(ancestor, descendant).unwrap_or(false)
+    };
+
+    repo.view()
+        .bookmarks()
+        .filter(|(_, targets)| {
+            let Some(local) = targets.local_target.as_normal() else {
+                return false;
+            };
+            if local == trunk || !is_ancestor(local, trunk) {
+                return false;
+            }
+            let mut tracked = targets
+                .remote_refs
+                .iter()
+                .filter(|(remote, remote_ref)| {
+                    *remote != git::REMOTE_NAME_FOR_LOCAL_GIT_REPO && remote_ref.is_tracked()
+                })
+                .peekable();
+            tracked.peek().is_some()
+                && tracked.all(|(_, remote_ref)| {
+                    remote_ref
+                        .target
+                        .as_normal()
+                        .is_some_and(|remote| is_ancestor(local, remote))
+                })
+        })
+        .count()
+}
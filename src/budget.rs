@@ -0,0 +1,110 @@
+//! Time budget for gathering the prompt
+//!
+//! With `--timeout-ms` (or `timeout-ms` in the config), the optional parts of
+//! the prompt are computed most important first, and whatever is left when
+//! the budget runs out is dropped. `degrade-order` names the parts to give up
+//! first; the rest follow in the default order:
+//!
+//! ```toml
+//! timeout-ms = 150
+//! degrade-order = ["file-count", "prefix", "bookmarks"]
+//! ```
+
+use crate::config::Config;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+/// An optional, possibly slow, part of the prompt
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Part {
+    /// The shortest unique change id prefix; without it the whole id is
+    /// highlighted
+    Prefix,
+    Bookmarks,
+    Signature,
+    StackDepth,
+    BehindTrunk,
+    Private,
+    StackConflicts,
+    StaleBookmarks,
+    /// The `[counters]` revsets
+    Counters,
+    /// The `[plugins]` commands
+    Plugins,
+    Untracked,
+    FileCount,
+}
+
+/// Default order parts are dropped in, cheapest to lose first
+const DEFAULT_DEGRADE_ORDER: [Part; 12] = [
+    Part::FileCount,
+    Part::Untracked,
+    Part::StaleBookmarks,
+    Part::Counters,
+    Part::Plugins,
+    Part::StackConflicts,
+    Part::Private,
+    Part::BehindTrunk,
+    Part::StackDepth,
+    Part::Signature,
+    Part::Prefix,
+    Part::Bookmarks,
+];
+
+impl Part {
+    /// Its name in `degrade-order`
+    pub fn name(self) -> &'static str {
+        match self {
+            Part::Prefix => "prefix",
+            Part::Bookmarks => "bookmarks",
+            Part::Signature => "signature",
+            Part::StackDepth => "stack-depth",
+            Part::BehindTrunk => "behind-trunk",
+            Part::Private => "private",
+            Part::StackConflicts => "stack-conflicts",
+            Part::StaleBookmarks => "stale-bookmarks",
+            Part::Counters => "counters",
+            Part::Plugins => "plugins",
+            Part::Untracked => "untracked",
+            Part::FileCount => "file-count",
+        }
+    }
+}
+
+pub struct Budget {
+    deadline: Option<Instant>,
+    /// Parts in the order they're computed, the last to be dropped first
+    parts: Vec<Part>,
+}
+
+impl Budget {
+    /// A budget of `timeout_ms` from `start`, or an unlimited one
+    pub fn new(start: Instant, timeout_ms: Option<u64>, config: &Config) -> Self {
+        let mut degrade_order: Vec<Part> = config.get("degrade-order").unwrap_or_default();
+        for part in DEFAULT_DEGRADE_ORDER {
+            if !degrade_order.contains(&part) {
+                degrade_order.push(part);
+            }
+        }
+        degrade_order.reverse();
+        Budget {
+            deadline: timeout_ms.map(|ms| start + Duration::from_millis(ms)),
+            parts: degrade_order,
+        }
+    }
+
+    /// Every part, most important first
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn is_spent(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
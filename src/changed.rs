@@ -0,0 +1,27 @@
+//! Cheap change detection for shells that cache the rendered prompt
+//!
+//! `jj-prompt changed --since <key>` prints a key identifying the repo and
+//! its current operation, read from the op heads directory without loading
+//! the repo. It exits 0 when the key differs from `<key>`, so a shell only
+//! re-renders after a jj command (or snapshot) has moved the repo.
+
+use crate::config;
+use std::fs;
+use std::path::Path;
+
+/// `<repo root>:<op head ids>`, or empty outside a repo
+pub fn key(repo_root: Option<&Path>) -> String {
+    let Some(repo_root) = repo_root else {
+        return String::new();
+    };
+    let mut heads: Vec<String> = config::repo_dir(repo_root)
+        .and_then(|dir| fs::read_dir(dir.join("op_heads").join("heads")).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    // Several heads exist briefly while concurrent operations are merged
+    heads.sort();
+    format!("{}:{}", repo_root.display(), heads.join(","))
+}
@@ -0,0 +1,82 @@
+//! Coalesce near-simultaneous renders of the same prompt
+//!
+//! The first invocation claims an in-flight marker in the repo's state
+//! directory and computes the prompt; invocations arriving while it runs
+//! wait for the marker to disappear and reuse the published result instead
+//! of each loading the repo themselves.
+
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often waiters check whether the in-flight render finished
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Markers older than this belong to a crashed or killed render
+const STALE_MARKER: Duration = Duration::from_secs(10);
+
+/// Run `compute`, or reuse the result of an identical render already in
+/// flight, waiting at most `max_wait` for it
+pub fn run(
+    dir: &Path,
+    key: u64,
+    max_wait: Duration,
+    compute: impl FnOnce() -> Option<String>,
+) -> Option<String> {
+    if fs::create_dir_all(dir).is_err() {
+        return compute();
+    }
+    let marker = dir.join(format!("inflight-{key:016x}"));
+    let result = dir.join(format!("result-{key:016x}"));
+
+    match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&marker)
+    {
+        Ok(_) => {
+            let output = compute();
+            if let Some(output) = &output {
+                let tmp = dir.join(format!("result-{key:016x}.{}", std::process::id()));
+                if fs::write(&tmp, output).is_ok() {
+                    fs::rename(&tmp, &result).ok();
+                }
+            }
+            fs::remove_file(&marker).ok();
+            output
+        }
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+            wait_for_result(&marker, &result, max_wait).or_else(compute)
+        }
+        Err(_) => compute(),
+    }
+}
+
+/// Wait for the marker to go away, then read a result published after it
+fn wait_for_result(marker: &Path, result: &Path, max_wait: Duration) -> Option<String> {
+    let started = modified(marker)?;
+    if started.elapsed().unwrap_or_default() > STALE_MARKER {
+        fs::remove_file(marker).ok();
+        return None;
+    }
+
+    let deadline = SystemTime::now() + max_wait;
+    while marker.exists() {
+        if SystemTime::now() >= deadline {
+            return None;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    // A result older than the marker is from a previous render
+    if modified(result)? < started {
+        return None;
+    }
+    fs::read_to_string(result).ok()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,125 @@
+//! Shell completions, including live values from the current repo
+//!
+//! The static script comes from clap_complete. Flags whose values live in the
+//! repo (bookmarks, ...) are completed by calling back into the hidden
+//! `__complete <kind>` subcommand, wired up by a small per-shell wrapper.
+
+use clap::{Command, ValueEnum};
+use clap_complete::Shell;
+use jj_lib::repo::Repo;
+use std::io::{self, Write};
+
+/// Repo-derived values the `__complete` subcommand can list
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Candidates {
+    /// Local bookmark names
+    Bookmarks,
+}
+
+impl Candidates {
+    fn name(self) -> &'static str {
+        match self {
+            Candidates::Bookmarks => "bookmarks",
+        }
+    }
+}
+
+/// Flags completed dynamically: (subcommand, long flag, values)
+const DYNAMIC_FLAGS: &[(Option<&str>, &str, Candidates)] = &[
+    (Some("url"), "bookmark", Candidates::Bookmarks),
+    (None, "revision", Candidates::Bookmarks),
+];
+
+/// Completion candidates of `kind` from the repo
+pub fn list_candidates(repo: &dyn Repo, kind: Candidates) -> Vec<String> {
+    match kind {
+        Candidates::Bookmarks => repo
+            .view()
+            .local_bookmarks()
+            .map(|(name, _)| name.as_str().to_string())
+            .collect(),
+    }
+}
+
+/// Write the completion script for `shell` to stdout
+pub fn generate(shell: Shell, cmd: &mut Command) {
+    let name = cmd.get_name().to_string();
+    // Buffer so a closed pipe (e.g. `| head`) isn't a panic inside clap_complete
+    let mut script = Vec::new();
+    clap_complete::generate(shell, cmd, &name, &mut script);
+    let wrapper = match shell {
+        Shell::Bash => bash_wrapper(&name),
+        Shell::Zsh => zsh_wrapper(&name),
+        Shell::Fish => fish_wrapper(&name),
+        _ => String::new(),
+    };
+    script.extend_from_slice(wrapper.as_bytes());
+    io::stdout().write_all(&script).ok();
+}
+
+fn bash_wrapper(name: &str) -> String {
+    let mut cases = String::new();
+    for (_, flag, kind) in DYNAMIC_FLAGS {
+        cases.push_str(&format!(
+            "        --{flag})\n            COMPREPLY=($(compgen -W \"$({name} __complete {} 2>/dev/null)\" -- \"$cur\"))\n            return 0\n            ;;\n",
+            kind.name()
+        ));
+    }
+    // Delegates to clap's generated function, which is named `_jj__prompt`
+    format!(
+        r#"
+_{ident}_dynamic() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    case "$prev" in
+{cases}    esac
+    _{clap_fn} "$@"
+}}
+
+if [[ "${{BASH_VERSINFO[0]}}" -eq 4 && "${{BASH_VERSINFO[1]}}" -ge 4 || "${{BASH_VERSINFO[0]}}" -gt 4 ]]; then
+    complete -F _{ident}_dynamic -o nosort -o bashdefault -o default {name}
+else
+    complete -F _{ident}_dynamic -o bashdefault -o default {name}
+fi
+"#,
+        ident = name.replace('-', "_"),
+        clap_fn = name.replace('-', "__"),
+    )
+}
+
+fn zsh_wrapper(name: &str) -> String {
+    let mut cases = String::new();
+    for (_, flag, kind) in DYNAMIC_FLAGS {
+        cases.push_str(&format!(
+            "        --{flag})\n            compadd -- ${{(f)\"$({name} __complete {} 2>/dev/null)\"}}\n            return\n            ;;\n",
+            kind.name()
+        ));
+    }
+    format!(
+        r#"
+_{ident}_dynamic() {{
+    case "${{words[CURRENT-1]}}" in
+{cases}    esac
+    _{name} "$@"
+}}
+compdef _{ident}_dynamic {name}
+"#,
+        ident = name.replace('-', "_"),
+    )
+}
+
+fn fish_wrapper(name: &str) -> String {
+    let mut lines = String::from("\n");
+    for (subcommand, flag, kind) in DYNAMIC_FLAGS {
+        let ident = name.replace('-', "_");
+        let condition = match subcommand {
+            Some(subcommand) => format!(" -n \"__fish_{ident}_using_subcommand {subcommand}\""),
+            None => format!(" -n \"__fish_{ident}_needs_command\""),
+        };
+        lines.push_str(&format!(
+            "complete -c {name}{condition} -l {flag} -f -r -a \"({name} __complete {} 2>/dev/null)\"\n",
+            kind.name()
+        ));
+    }
+    lines
+}
@@ -0,0 +1,183 @@
+//! jj-prompt configuration files
+//!
+//! Settings come from the user config (`$JJ_PROMPT_CONFIG`, or
+//! `~/.config/jj-prompt/config.toml`) and can be overridden per repo in
+//! `.jj/repo/jj-prompt.toml`. Command-line flags take precedence over both.
+
+use crate::debug::Traced;
+use jj_lib::config::{
+    self, ConfigGetResultExt, ConfigLayer, ConfigResolutionContext, ConfigSource, StackedConfig,
+};
+use jj_lib::settings::UserSettings;
+use serde::de::DeserializeOwned;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the per-repo config inside the repo directory
+const REPO_CONFIG_FILE: &str = "jj-prompt.toml";
+
+/// Layered jj-prompt settings
+pub struct Config {
+    stacked: StackedConfig,
+}
+
+impl Config {
+    /// Load user config and, if inside a repo, its per-repo overrides.
+    /// Unreadable or malformed files are skipped so the prompt still renders.
+    pub fn load(repo_root: Option<&Path>) -> Self {
+        let mut stacked = StackedConfig::empty();
+        if let Some(path) = user_config_path() {
+            add_file_layer(&mut stacked, ConfigSource::User, path);
+        }
+        if let Some(repo_dir) = repo_root.and_then(repo_dir) {
+            add_file_layer(
+                &mut stacked,
+                ConfigSource::Repo,
+                repo_dir.join(REPO_CONFIG_FILE),
+            );
+        }
+        Config { stacked }
+    }
+
+    /// Look up `key` (dotted path), returning None if unset or invalid
+    pub fn get<T: DeserializeOwned>(&self, key: &'static str) -> Option<T> {
+        self.stacked.get(key).optional().ok().flatten()
+    }
+
+    /// Entries of the table at `key` in the order they're declared, skipping
+    /// invalid ones
+    pub fn table<T: DeserializeOwned>(&self, key: &'static str) -> Vec<(String, T)> {
+        self.stacked
+            .table_keys(key)
+            .filter_map(|name| {
+                let value = self.stacked.get([key, name]).traced("read config")?;
+                Some((name.to_string(), value))
+            })
+            .collect()
+    }
+}
+
+fn add_file_layer(stacked: &mut StackedConfig, source: ConfigSource, path: PathBuf) {
+    if path.is_file()
+        && let Some(layer) = ConfigLayer::load_from_file(source, path).traced("read config")
+    {
+        stacked.add_layer(layer);
+    }
+}
+
+/// `$JJ_PROMPT_CONFIG`, else `$XDG_CONFIG_HOME/jj-prompt/config.toml`
+fn user_config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("JJ_PROMPT_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    config_home().map(|dir| dir.join("jj-prompt").join("config.toml"))
+}
+
+/// `$XDG_CONFIG_HOME`, falling back to `~/.config` on every platform
+pub fn config_home() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+/// Expand a leading `~/` so config values can be written portably
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Resolve `.jj/repo`, which is a pointer file in secondary workspaces
+pub fn repo_dir(workspace_root: &Path) -> Option<PathBuf> {
+    let jj_dir = workspace_root.join(".jj");
+    let repo_dir = jj_dir.join("repo");
+    if repo_dir.is_file() {
+        let target = fs::read_to_string(&repo_dir).ok()?;
+        dunce::canonicalize(jj_dir.join(target.trim())).ok()
+    } else {
+        Some(repo_dir)
+    }
+}
+
+/// jj's own settings, layered like jj-cli does: defaults, user config, repo
+/// config, workspace config, then `JJ_USER`/`JJ_EMAIL` overrides, with
+/// `[[--scope]]` tables applied where their `--when` matches the workspace
+pub fn load_jj_settings(workspace_root: &Path) -> Option<UserSettings> {
+    let mut config = StackedConfig::with_defaults();
+    // jj's own defaults, which its templates and their aliases read
+    #[cfg(feature = "templates")]
+    config.extend_layers(jj_cli::config::default_config_layers());
+    let mut defaults = ConfigLayer::empty(ConfigSource::Default);
+    defaults.set_value("user.name", "").ok()?;
+    defaults.set_value("user.email", "").ok()?;
+    config.add_layer(defaults);
+
+    for path in jj_user_config_paths() {
+        if path.is_dir() {
+            config.load_dir(ConfigSource::User, &path).ok();
+        } else {
+            add_file_layer(&mut config, ConfigSource::User, path);
+        }
+    }
+    if let Some(repo_dir) = repo_dir(workspace_root) {
+        add_file_layer(
+            &mut config,
+            ConfigSource::Repo,
+            repo_dir.join("config.toml"),
+        );
+    }
+    add_file_layer(
+        &mut config,
+        ConfigSource::Workspace,
+        workspace_root.join(".jj").join("workspace-config.toml"),
+    );
+
+    let mut overrides = ConfigLayer::empty(ConfigSource::EnvOverrides);
+    if let Ok(name) = env::var("JJ_USER") {
+        overrides.set_value("user.name", name).ok()?;
+    }
+    if let Ok(email) = env::var("JJ_EMAIL") {
+        overrides.set_value("user.email", email).ok()?;
+    }
+    config.add_layer(overrides);
+
+    // jj matches `--when.repositories` against physical paths
+    let workspace_path = dunce::canonicalize(workspace_root).ok()?;
+    let repo_path = repo_dir(&workspace_path);
+    let home_dir = env::var_os("HOME").map(PathBuf::from);
+    let hostname = whoami::fallible::hostname().unwrap_or_default();
+    let context = ConfigResolutionContext {
+        home_dir: home_dir.as_deref(),
+        repo_path: repo_path.as_deref(),
+        workspace_path: Some(&workspace_path),
+        // Not a jj command, so `--when.commands` scopes never apply
+        command: None,
+        hostname: &hostname,
+    };
+    let config = config::resolve(&config, &context).traced("resolve jj config")?;
+    UserSettings::from_config(config).traced("load jj settings")
+}
+
+/// `$JJ_CONFIG`, else `~/.jjconfig.toml` (if present) plus
+/// `~/.config/jj/config.toml` and `~/.config/jj/conf.d`
+fn jj_user_config_paths() -> Vec<PathBuf> {
+    if let Some(paths) = env::var_os("JJ_CONFIG") {
+        return env::split_paths(&paths)
+            .filter(|path| !path.as_os_str().is_empty())
+            .collect();
+    }
+    let mut paths = Vec::new();
+    if let Some(home) = env::var_os("HOME") {
+        let legacy = PathBuf::from(home).join(".jjconfig.toml");
+        if legacy.exists() {
+            paths.push(legacy);
+        }
+    }
+    if let Some(config_home) = config_home() {
+        paths.push(config_home.join("jj").join("config.toml"));
+        paths.push(config_home.join("jj").join("conf.d"));
+    }
+    paths
+}
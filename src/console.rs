@@ -0,0 +1,226 @@
+//! Terminal capabilities, and Windows console setup for ANSI colors
+//!
+//! Capabilities are read from the environment, so the prompt never waits on
+//! the terminal. Only `jj-prompt background` queries the terminal itself.
+//!
+//! Windows consoles only interpret escape codes with virtual terminal
+//! processing enabled. The prompt's stdout is usually captured by the shell,
+//! so the mode is set on the console itself (`CONOUT$`), where it stays on
+//! while the shell prints the prompt. If it can't be enabled (old Windows, no
+//! console), the prompt is rendered without color.
+
+use crate::theme::{Background, ColorDepth};
+use std::time::Duration;
+
+/// Make sure escape codes written to the terminal are interpreted, returning
+/// whether they will be
+#[cfg(windows)]
+pub fn enable_ansi() -> bool {
+    use std::env;
+    use std::ffi::c_void;
+    use std::fs::OpenOptions;
+    use std::os::windows::io::AsRawHandle;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+    }
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    // mintty and other terminal emulators that set TERM handle escapes themselves
+    if env::var_os("TERM").is_some() {
+        return true;
+    }
+    let Ok(console) = OpenOptions::new().read(true).write(true).open("CONOUT$") else {
+        return false;
+    };
+    let handle = console.as_raw_handle();
+    let mut mode = 0;
+    // SAFETY: `handle` is an open console handle for the duration of both calls
+    unsafe {
+        GetConsoleMode(handle, &mut mode) != 0
+            && (mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0)
+    }
+}
+
+#[cfg(not(windows))]
+pub fn enable_ansi() -> bool {
+    true
+}
+
+/// Whether the terminal is known to support OSC 8 hyperlinks, going by the
+/// environment variables terminals set. `FORCE_HYPERLINK=1`/`0` overrides.
+pub fn supports_hyperlinks() -> bool {
+    use std::env;
+
+    if let Some(force) = env::var_os("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+    let var = |name: &str| env::var(name).unwrap_or_default();
+    matches!(
+        var("TERM_PROGRAM").as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+    ) || ["WT_SESSION", "KITTY_WINDOW_ID", "KONSOLE_VERSION", "DOMTERM"]
+        .iter()
+        .any(|name| env::var_os(name).is_some())
+        // GNOME Terminal and other VTE terminals since 0.50
+        || var("VTE_VERSION").parse::<u32>().is_ok_and(|version| version >= 5000)
+        || matches!(
+            var("TERM").as_str(),
+            "xterm-kitty" | "xterm-ghostty" | "alacritty" | "foot" | "wezterm"
+        )
+}
+
+/// Whether the terminal takes 24-bit colors, as advertised in `COLORTERM`
+pub fn supports_truecolor() -> bool {
+    std::env::var("COLORTERM").is_ok_and(|value| matches!(value.as_str(), "truecolor" | "24bit"))
+}
+
+/// Colors the terminal shows: 24-bit per `COLORTERM`, else the 256-color
+/// palette unless `TERM` names a terminal without it (`xterm`, `linux`, ...)
+pub fn color_depth() -> ColorDepth {
+    if supports_truecolor() {
+        return ColorDepth::Truecolor;
+    }
+    match std::env::var("TERM") {
+        // Unset on Windows, whose consoles take 256 colors
+        Err(_) => ColorDepth::Indexed,
+        Ok(term)
+            if term.contains("256color")
+                || term.ends_with("-direct")
+                || matches!(
+                    term.as_str(),
+                    "xterm-kitty" | "xterm-ghostty" | "alacritty" | "foot" | "wezterm"
+                ) =>
+        {
+            ColorDepth::Indexed
+        }
+        Ok(_) => ColorDepth::Basic,
+    }
+}
+
+/// `--color auto`: `NO_COLOR` turns colors off and `CLICOLOR_FORCE` on.
+/// Otherwise color if stdout or stderr is a terminal (`$(jj-prompt)` only
+/// captures stdout), or under starship, which renders the escapes itself.
+pub fn auto_color() -> bool {
+    use std::env;
+    use std::io::{self, IsTerminal};
+
+    let set = |name: &str| env::var_os(name).is_some_and(|value| !value.is_empty());
+    if set("NO_COLOR") {
+        return false;
+    }
+    if set("CLICOLOR_FORCE") && env::var_os("CLICOLOR_FORCE").as_deref() != Some("0".as_ref()) {
+        return true;
+    }
+    io::stdout().is_terminal() || io::stderr().is_terminal() || set("STARSHIP_SHELL")
+}
+
+/// The background from `JJ_PROMPT_BACKGROUND` (`light`/`dark`), else from
+/// `COLORFGBG` (`fg;bg` palette indices, set by rxvt, Konsole, iTerm2, ...)
+pub fn background_hint() -> Option<Background> {
+    use std::env;
+
+    match env::var("JJ_PROMPT_BACKGROUND").as_deref() {
+        Ok("light") => return Some(Background::Light),
+        Ok("dark") => return Some(Background::Dark),
+        _ => {}
+    }
+    let colorfgbg = env::var("COLORFGBG").ok()?;
+    let bg: u8 = colorfgbg.rsplit(';').next()?.parse().ok()?;
+    // White (7) and the bright colors but dark gray (8) are light
+    Some(if bg == 7 || bg > 8 {
+        Background::Light
+    } else {
+        Background::Dark
+    })
+}
+
+/// Ask the terminal for its background color with an OSC 11 query, waiting
+/// at most `timeout` for the answer
+#[cfg(unix)]
+pub fn query_background(timeout: Duration) -> Option<Background> {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::mem::MaybeUninit;
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+    let mut original = MaybeUninit::<libc::termios>::uninit();
+    // SAFETY: `fd` is open and `original` is written before it's read
+    let original = unsafe {
+        if libc::tcgetattr(fd, original.as_mut_ptr()) != 0 {
+            return None;
+        }
+        original.assume_init()
+    };
+    // Unbuffered and unechoed, so the answer can be read without a newline
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 0;
+    raw.c_cc[libc::VTIME] = 0;
+    // SAFETY: `fd` is open and `raw` is a valid termios
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+        return None;
+    }
+
+    let mut answer = Vec::new();
+    if tty
+        .write_all(b"\x1b]11;?\x07")
+        .and_then(|()| tty.flush())
+        .is_ok()
+    {
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0; 64];
+        // Answers end in BEL or ST (ESC \\)
+        while !answer.ends_with(b"\x07") && !answer.ends_with(b"\x1b\\") {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let mut poll = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `poll` is a single valid pollfd
+            let ready = unsafe { libc::poll(&mut poll, 1, remaining.as_millis() as libc::c_int) };
+            if ready <= 0 {
+                break;
+            }
+            match tty.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => answer.extend_from_slice(&buf[..n]),
+            }
+        }
+    }
+    // SAFETY: restores the settings read above
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) };
+    parse_osc11(&String::from_utf8_lossy(&answer))
+}
+
+#[cfg(not(unix))]
+pub fn query_background(_timeout: Duration) -> Option<Background> {
+    None
+}
+
+/// `ESC ] 11 ; rgb:RRRR/GGGG/BBBB`, with 1 to 4 hex digits per channel
+fn parse_osc11(answer: &str) -> Option<Background> {
+    let rgb = answer.split("rgb:").nth(1)?;
+    let rgb = rgb
+        .trim_end_matches(['\x07', '\\'])
+        .trim_end_matches('\x1b');
+    let mut channels = rgb.split('/').map(|hex| {
+        let max = 16f64.powi(hex.len() as i32) - 1.0;
+        u32::from_str_radix(hex, 16)
+            .ok()
+            .map(|value| f64::from(value) / max)
+    });
+    let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+    Some(Background::from_rgb(r, g, b))
+}
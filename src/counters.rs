@@ -0,0 +1,35 @@
+//! Counter segments declared in the config
+//!
+//! Each `[counters]` entry is a revset, and the prompt shows how many commits
+//! match it after its icon, hidden while there are none:
+//!
+//! ```toml
+//! [counters]
+//! conflicts = { revset = "conflicts() & mutable()", icon = "×" }
+//! wip = { revset = 'mine() & description(glob:"wip:*")', icon = "✎" }
+//! ```
+
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+
+/// A `[counters]` entry
+#[derive(Deserialize)]
+pub struct Counter {
+    pub revset: String,
+    #[serde(default)]
+    pub icon: String,
+}
+
+/// A counter's value, named as in the config
+#[derive(Serialize)]
+pub struct Count {
+    pub name: String,
+    #[serde(skip)]
+    pub icon: String,
+    pub count: usize,
+}
+
+/// The counters in the order they're declared
+pub fn load(config: &Config) -> Vec<(String, Counter)> {
+    config.table("counters")
+}
@@ -0,0 +1,56 @@
+//! `--debug`: say on stderr why a part of the prompt is missing
+//!
+//! Failures normally turn into a quietly shorter (or empty) prompt. With
+//! `--debug`, each one names the step that failed and its underlying error:
+//!
+//! ```text
+//! jj-prompt: load workspace: The working copy at ... is stale
+//! ```
+
+use std::error::Error;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Report that `step` failed because of `reason`
+pub fn log(step: &str, reason: impl Display) {
+    tracing::warn!(step, %reason);
+    if ENABLED.load(Ordering::Relaxed) {
+        eprintln!("jj-prompt: {step}: {reason}");
+    }
+}
+
+/// `.ok()` that reports what went wrong
+pub trait Traced<T> {
+    fn traced(self, step: &str) -> Option<T>;
+}
+
+impl<T, E: Error> Traced<T> for Result<T, E> {
+    fn traced(self, step: &str) -> Option<T> {
+        self.inspect_err(|err| {
+            // The chain of causes, as jj prints it
+            let mut reason = err.to_string();
+            let mut source = err.source();
+            while let Some(cause) = source {
+                reason = format!("{reason}: {cause}");
+                source = cause.source();
+            }
+            log(step, reason);
+        })
+        .ok()
+    }
+}
+
+impl<T> Traced<T> for Option<T> {
+    fn traced(self, step: &str) -> Option<T> {
+        if self.is_none() {
+            log(step, "not found");
+        }
+        self
+    }
+}
@@ -0,0 +1,79 @@
+//! Scripted prompt states with stable fake ids
+//!
+//! `jj-prompt demo` renders these without a repo, for documentation, theme
+//! galleries, and terminal recordings.
+
+use crate::PromptInfo;
+use clap::ValueEnum;
+
+/// A scripted prompt state
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DemoState {
+    /// Empty working copy on a bookmark
+    Clean,
+    /// Edited files and untracked files
+    Dirty,
+    /// Conflicted working copy
+    Conflict,
+    /// Change id shared by several visible commits
+    Divergent,
+    /// Trunk has moved on since the stack was based
+    BehindTrunk,
+}
+
+impl DemoState {
+    /// Prompt info for this state
+    pub fn info(self) -> PromptInfo {
+        let base = PromptInfo {
+            change_id: "kmtq".to_string(),
+            prefix_len: 1,
+            description: "(no description set)".to_string(),
+            age_secs: 25 * 60,
+            ..PromptInfo::default()
+        };
+        match self {
+            DemoState::Clean => PromptInfo {
+                bookmarks: vec!["main".to_string()],
+                ..base
+            },
+            DemoState::Dirty => PromptInfo {
+                change_id: "vrxs".to_string(),
+                prefix_len: 2,
+                description: "wip: retry failed fetches".to_string(),
+                wip_prefix_len: Some(4),
+                topic: Some("net".to_string()),
+                file_count: Some(3),
+                untracked_count: Some(1),
+                stack_depth: Some(2),
+                age_secs: 3 * 3600,
+                ..base
+            },
+            DemoState::Conflict => PromptInfo {
+                change_id: "zpwl".to_string(),
+                description: "Rebase onto main".to_string(),
+                has_conflict: true,
+                conflict_count: 2,
+                file_count: Some(2),
+                stack_depth: Some(3),
+                ..base
+            },
+            DemoState::Divergent => PromptInfo {
+                change_id: "qnoy".to_string(),
+                description: "Fix parser edge case".to_string(),
+                divergent_count: Some(2),
+                file_count: Some(1),
+                ..base
+            },
+            DemoState::BehindTrunk => PromptInfo {
+                change_id: "tyuk".to_string(),
+                bookmarks: vec!["feature".to_string()],
+                description: "Refactor config loading".to_string(),
+                file_count: Some(4),
+                stack_depth: Some(2),
+                behind_trunk: Some(5),
+                age_secs: 2 * 86400,
+                ..base
+            },
+        }
+    }
+}
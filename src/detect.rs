@@ -0,0 +1,119 @@
+//! `jj-prompt detect`: what kind of repo the directory is in
+//!
+//! Answered from the files under `.jj`, without loading the repo, so shell
+//! scripts can branch on it as cheaply as on the exit status. `--json` adds
+//! what the workspace knows (its name, the backend, staleness), still without
+//! the commit index.
+
+use crate::config;
+use crate::debug::Traced;
+use crate::values::SCHEMA_VERSION;
+use jj_lib::workspace::Workspace;
+use pollster::FutureExt as _;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy)]
+pub enum RepoKind {
+    /// Outside any jj workspace
+    None,
+    Jj,
+    /// jj and git share the working tree
+    Colocated,
+}
+
+impl RepoKind {
+    /// The kind of the workspace at `repo_root`, if any
+    pub fn of(repo_root: Option<&Path>) -> Self {
+        match repo_root {
+            None => RepoKind::None,
+            Some(root) if is_colocated(root) => RepoKind::Colocated,
+            Some(_) => RepoKind::Jj,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RepoKind::None => "none",
+            RepoKind::Jj => "jj",
+            RepoKind::Colocated => "colocated",
+        }
+    }
+}
+
+/// Whether the git repo backing the store is `repo_root/.git`
+fn is_colocated(repo_root: &Path) -> bool {
+    let dot_git = repo_root.join(".git");
+    if !dot_git.exists() {
+        return false;
+    }
+    let Some(store) = config::repo_dir(repo_root).map(|repo| repo.join("store")) else {
+        return false;
+    };
+    // Relative to the store, e.g. `../../../.git`; `git` inside it otherwise
+    let Ok(target) = fs::read_to_string(store.join("git_target")) else {
+        return false;
+    };
+    let git_dir = store.join(target.trim());
+    match (git_dir.canonicalize(), dot_git.canonicalize()) {
+        (Ok(git_dir), Ok(dot_git)) => git_dir == dot_git,
+        _ => false,
+    }
+}
+
+/// `detect --json`
+#[derive(Serialize)]
+struct Probe<'a> {
+    schema_version: u32,
+    root: Option<&'a Path>,
+    kind: &'static str,
+    workspace: Option<&'a str>,
+    /// `git`, or `Simple` for jj's test backend
+    backend: Option<&'a str>,
+    /// Whether `jj workspace update-stale` is needed; unknown while
+    /// concurrent operations are unmerged
+    stale: Option<bool>,
+}
+
+/// A JSON object describing the workspace at `repo_root`, all null but `kind`
+/// outside one
+pub fn json(repo_root: Option<&Path>, workspace: Option<&Workspace>) -> String {
+    serde_json::to_string(&Probe {
+        schema_version: SCHEMA_VERSION,
+        root: repo_root,
+        kind: RepoKind::of(repo_root).name(),
+        workspace: workspace.map(|ws| ws.workspace_name().as_str()),
+        backend: workspace.map(|ws| ws.repo_loader().store().backend().name()),
+        stale: workspace.and_then(is_stale),
+    })
+    .unwrap_or_default()
+}
+
+/// Whether another workspace rewrote this one's commit since its files were
+/// last updated, judged from the single op head's view
+fn is_stale(workspace: &Workspace) -> Option<bool> {
+    let wc = workspace.working_copy();
+    let loader = workspace.repo_loader();
+    let op_heads = loader
+        .op_heads_store()
+        .get_op_heads()
+        .block_on()
+        .traced("read op heads")?;
+    let [op_id] = op_heads.as_slice() else {
+        return None;
+    };
+    if wc.operation_id() == op_id {
+        return Some(false);
+    }
+    let view = loader
+        .load_operation(op_id)
+        .and_then(|op| op.view())
+        .traced("read view")?;
+    let wc_id = view
+        .get_wc_commit_id(wc.workspace_name())
+        .traced("working-copy commit")?;
+    let commit = loader.store().get_commit(wc_id).traced("read commit")?;
+    let tree = wc.tree().traced("read working copy")?;
+    Some(tree.tree_ids() != commit.tree_ids())
+}
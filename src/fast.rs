@@ -0,0 +1,60 @@
+//! The `--fast` prompt: change id, bookmarks, and description only
+//!
+//! Building a `ReadonlyRepo` loads the commit index, which dominates the time
+//! on large repos. None of these segments needs it: the working-copy commit
+//! and its bookmarks come straight from the single op head's view, and the
+//! whole change id is highlighted since the shortest unique prefix would take
+//! an index lookup.
+
+use crate::bookmarks;
+use crate::debug::{self, Traced};
+use crate::text::sanitize;
+use crate::{Cli, PromptInfo};
+use jj_lib::hex_util::encode_reverse_hex;
+use jj_lib::object_id::ObjectId;
+use jj_lib::workspace::Workspace;
+use pollster::FutureExt as _;
+
+/// Read the prompt from the store, or `None` if there are several op heads
+/// (concurrent operations jj hasn't merged yet) or anything fails to load
+pub fn gather(workspace: &Workspace, cli: &Cli) -> Option<PromptInfo> {
+    let loader = workspace.repo_loader();
+    let op_heads = loader
+        .op_heads_store()
+        .get_op_heads()
+        .block_on()
+        .traced("read op heads")?;
+    let [op_id] = op_heads.as_slice() else {
+        debug::log("fast path", "several op heads, loading the repo instead");
+        return None;
+    };
+    let view = loader
+        .load_operation(op_id)
+        .and_then(|op| op.view())
+        .traced("read view")?;
+    let wc_id = view
+        .get_wc_commit_id(workspace.workspace_name())
+        .traced("working-copy commit")?;
+    let commit = loader.store().get_commit(wc_id).traced("read commit")?;
+
+    let mut change_id = encode_reverse_hex(commit.change_id().as_bytes());
+    change_id.truncate(cli.id_length);
+    let mut bookmarks: Vec<String> = view
+        .local_bookmarks_for_commit(wc_id)
+        .map(|(name, _)| sanitize(name.as_str()).into_owned())
+        .collect();
+    if cli.bookmarks_max.is_some() || cli.max_width.is_some() {
+        bookmarks::sort_trunk_last(&mut bookmarks);
+    }
+    let description = commit.description().lines().next().unwrap_or("").trim();
+
+    Some(PromptInfo {
+        prefix_len: change_id.len(),
+        change_id,
+        bookmark_urls: vec![None; bookmarks.len()],
+        bookmarks,
+        description: sanitize(description).into_owned(),
+        has_conflict: commit.has_conflict(),
+        ..PromptInfo::default()
+    })
+}
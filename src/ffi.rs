@@ -0,0 +1,81 @@
+//! C API, for hosts that render the prompt in-process
+//!
+//! Built with `--features ffi` as a shared library:
+//!
+//! ```sh
+//! cargo rustc --release --lib --features ffi --crate-type cdylib
+//! ```
+//!
+//! ```c
+//! char *jj_prompt_render(const char *path, const char *options);
+//! void jj_prompt_free(char *prompt);
+//! ```
+//!
+//! `options` is NULL or a JSON array of command-line arguments, e.g.
+//! `["--output", "nvim", "--no-file-count"]`. The result is what `jj-prompt`
+//! would print in `path`, or NULL outside a repo, on failure, or when
+//! unchanged with `--if-changed`.
+
+use crate::debug::{self, Traced};
+use crate::{run_prompt, Cli, Command};
+use clap::Parser;
+use std::ffi::{c_char, CStr, CString};
+use std::iter;
+use std::panic;
+use std::path::Path;
+use std::ptr;
+
+/// The prompt for the directory `path`, rendered per `options`; free it with
+/// `jj_prompt_free`
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string, and `options` one or NULL.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn jj_prompt_render(
+    path: *const c_char,
+    options: *const c_char,
+) -> *mut c_char {
+    if path.is_null() {
+        return ptr::null_mut();
+    }
+    // SAFETY: both are NUL-terminated when not NULL, per the contract above
+    let path = unsafe { CStr::from_ptr(path) };
+    let options = (!options.is_null()).then(|| unsafe { CStr::from_ptr(options) });
+    // A panic mustn't unwind into the host
+    panic::catch_unwind(|| render(path, options))
+        .ok()
+        .flatten()
+        .and_then(|prompt| CString::new(prompt).ok())
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Free a prompt returned by `jj_prompt_render`
+///
+/// # Safety
+///
+/// `prompt` must come from `jj_prompt_render` and not be freed yet, or be
+/// NULL.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn jj_prompt_free(prompt: *mut c_char) {
+    if !prompt.is_null() {
+        // SAFETY: allocated by `CString::into_raw` in `jj_prompt_render`
+        drop(unsafe { CString::from_raw(prompt) });
+    }
+}
+
+fn render(path: &CStr, options: Option<&CStr>) -> Option<String> {
+    let path = Path::new(path.to_str().traced("read path")?);
+    let args: Vec<String> = match options {
+        Some(options) => serde_json::from_slice(options.to_bytes()).traced("read options")?,
+        None => Vec::new(),
+    };
+    let cli = Cli::try_parse_from(iter::once("jj-prompt".to_string()).chain(args))
+        .traced("read options")?;
+    // Subcommands print their results rather than rendering a prompt
+    if !matches!(cli.command, None | Some(Command::Prompt)) {
+        debug::log("read options", "only the prompt can be rendered");
+        return None;
+    }
+    run_prompt(path, &cli).0
+}
@@ -0,0 +1,214 @@
+//! Forge URLs built from per-repo templates
+//!
+//! Templates live under `[urls]` in the config and may reference
+//! `{change_id}`, `{commit_id}`, `{bookmark}` and `{ticket}`:
+//!
+//! ```toml
+//! [urls]
+//! change = "https://review.example.com/q/{change_id}"
+//! commit = "https://github.com/org/repo/commit/{commit_id}"
+//! bookmark = "https://github.com/org/repo/tree/{bookmark}"
+//! ticket = "https://jira.example.com/browse/{ticket}"
+//! ```
+//!
+//! Unset templates are derived from the git remote when it's on GitHub,
+//! GitLab or Gerrit:
+//!
+//! ```toml
+//! [forge]
+//! remote = "upstream"  # remote to link to (default: origin, or the only one)
+//! type = "gitlab"      # for self-hosted forges not named after the software
+//! detect = false       # only use `[urls]`
+//! ```
+
+use crate::config::Config;
+use clap::ValueEnum;
+use jj_lib::store::Store;
+use serde::Deserialize;
+
+/// What a URL points at
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum UrlKind {
+    /// The change (e.g. a Gerrit review)
+    #[default]
+    Change,
+    /// The commit
+    Commit,
+    /// A bookmark (branch)
+    Bookmark,
+    /// The ticket from `ticket-pattern`, in the issue tracker
+    Ticket,
+}
+
+impl UrlKind {
+    fn config_key(self) -> &'static str {
+        match self {
+            UrlKind::Change => "urls.change",
+            UrlKind::Commit => "urls.commit",
+            UrlKind::Bookmark => "urls.bookmark",
+            UrlKind::Ticket => "urls.ticket",
+        }
+    }
+}
+
+/// Values substituted into URL templates
+pub struct UrlVars<'a> {
+    pub change_id: &'a str,
+    pub commit_id: &'a str,
+    pub bookmark: Option<&'a str>,
+    pub ticket: Option<&'a str>,
+}
+
+/// Build the URL of `kind` from the configured template, else the one
+/// derived from the remote
+pub fn build_url(
+    config: &Config,
+    remote: &RemoteTemplates,
+    kind: UrlKind,
+    vars: &UrlVars,
+) -> Option<String> {
+    let template = config
+        .get::<String>(kind.config_key())
+        .or_else(|| remote.get(kind).map(str::to_string))?;
+    expand_template(&template, vars)
+}
+
+/// Forge software, which decides the web URL layout
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ForgeType {
+    Github,
+    Gitlab,
+    Gerrit,
+}
+
+/// URL templates derived from the repo's git remote
+#[derive(Default)]
+pub struct RemoteTemplates {
+    commit: Option<String>,
+    bookmark: Option<String>,
+}
+
+impl RemoteTemplates {
+    /// Templates for the configured (or only) remote of a git-backed repo,
+    /// colocated or not; empty if there's no recognizable forge
+    pub fn detect(config: &Config, store: &Store) -> Self {
+        if !config.get("forge.detect").unwrap_or(true) {
+            return RemoteTemplates::default();
+        }
+        let Ok(git_repo) = jj_lib::git::get_git_repo(store) else {
+            return RemoteTemplates::default();
+        };
+        let remote = config.get::<String>("forge.remote").or_else(|| {
+            let names = git_repo.remote_names();
+            if names.iter().any(|name| name.as_ref() == "origin") {
+                Some("origin".to_string())
+            } else if names.len() == 1 {
+                names.first().map(|name| name.to_string())
+            } else {
+                None
+            }
+        });
+        let url = remote.and_then(|remote| {
+            let url = git_repo
+                .config_snapshot()
+                .string(format!("remote.{remote}.url"))?;
+            Some(url.to_string())
+        });
+        url.and_then(|url| RemoteTemplates::from_url(&url, config.get("forge.type")))
+            .unwrap_or_default()
+    }
+
+    fn from_url(url: &str, forge_type: Option<ForgeType>) -> Option<Self> {
+        let (host, port, path) = parse_remote_url(url)?;
+        let forge_type = forge_type.or_else(|| {
+            if host.contains("github") {
+                Some(ForgeType::Github)
+            } else if host.contains("gitlab") {
+                Some(ForgeType::Gitlab)
+            } else if port == Some(29418) || host.contains("gerrit") || path.starts_with("a/") {
+                Some(ForgeType::Gerrit)
+            } else {
+                None
+            }
+        })?;
+        let base = format!("https://{host}/{path}");
+        Some(match forge_type {
+            ForgeType::Github => RemoteTemplates {
+                commit: Some(format!("{base}/commit/{{commit_id}}")),
+                bookmark: Some(format!("{base}/tree/{{bookmark}}")),
+            },
+            ForgeType::Gitlab => RemoteTemplates {
+                commit: Some(format!("{base}/-/commit/{{commit_id}}")),
+                bookmark: Some(format!("{base}/-/tree/{{bookmark}}")),
+            },
+            // Gerrit finds reviews by commit; bookmarks have no web page
+            ForgeType::Gerrit => RemoteTemplates {
+                commit: Some(format!("https://{host}/q/{{commit_id}}")),
+                bookmark: None,
+            },
+        })
+    }
+
+    fn get(&self, kind: UrlKind) -> Option<&str> {
+        match kind {
+            UrlKind::Commit => self.commit.as_deref(),
+            UrlKind::Bookmark => self.bookmark.as_deref(),
+            UrlKind::Change | UrlKind::Ticket => None,
+        }
+    }
+}
+
+/// Host, port and repo path (without `.git`) of an `https://`, `ssh://` or
+/// scp-style (`git@host:org/repo`) remote URL
+fn parse_remote_url(url: &str) -> Option<(String, Option<u16>, String)> {
+    let (authority, path) = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        // scp-style: the first `:` ends the host
+        None => url.split_once(':')?,
+    };
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let (host, port) = match host_port.split_once(':') {
+        Some((host, port)) => (host, port.parse().ok()),
+        None => (host_port, None),
+    };
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some((host.to_string(), port, path.to_string()))
+}
+
+/// Substitute `{name}` placeholders; None if a referenced value is missing
+fn expand_template(template: &str, vars: &UrlVars) -> Option<String> {
+    let mut url = template
+        .replace("{change_id}", vars.change_id)
+        .replace("{commit_id}", vars.commit_id);
+    if url.contains("{bookmark}") {
+        url = url.replace("{bookmark}", &escape_controls(vars.bookmark?));
+    }
+    if url.contains("{ticket}") {
+        url = url.replace("{ticket}", &escape_controls(vars.ticket?));
+    }
+    Some(url)
+}
+
+/// Percent-encode control characters and spaces, which would end an OSC 8
+/// link early, leaving `/` and the rest readable
+fn escape_controls(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_control() || c == ' ' {
+            let mut utf8 = [0; 4];
+            for byte in c.encode_utf8(&mut utf8).bytes() {
+                escaped.push_str(&format!("%{byte:02X}"));
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
@@ -0,0 +1,50 @@
+//! Session hints for a fallback prompt when the repo can't be loaded
+//!
+//! `jj-prompt hints` prints shell exports identifying the repo and change id.
+//! Evaluated from a precmd hook, they let a later prompt render a dimmed
+//! "last known" prompt during transient lock or I/O failures.
+
+use clap::ValueEnum;
+use std::env;
+use std::path::Path;
+
+const ROOT_VAR: &str = "JJ_PROMPT_HINT_ROOT";
+const CHANGE_ID_VAR: &str = "JJ_PROMPT_HINT_CHANGE_ID";
+
+/// Syntax of the printed exports
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum HintShell {
+    /// `export VAR='value'` (bash, zsh)
+    #[default]
+    Posix,
+    /// `set -gx VAR 'value'`
+    Fish,
+}
+
+/// Shell statements exporting the hints for `repo_root` and `change_id`
+pub fn exports(shell: HintShell, repo_root: &Path, change_id: &str) -> String {
+    let root = dunce::canonicalize(repo_root).unwrap_or_else(|_| repo_root.to_path_buf());
+    [
+        (ROOT_VAR, root.to_string_lossy().into_owned()),
+        (CHANGE_ID_VAR, change_id.to_string()),
+    ]
+    .iter()
+    .map(|(var, value)| match shell {
+        HintShell::Posix => format!("export {var}='{}'\n", value.replace('\'', r"'\''")),
+        HintShell::Fish => format!(
+            "set -gx {var} '{}'\n",
+            value.replace('\\', r"\\").replace('\'', r"\'")
+        ),
+    })
+    .collect()
+}
+
+/// Change id from the session hints, if they were recorded for `repo_root`
+pub fn last_known_change_id(repo_root: &Path) -> Option<String> {
+    let hinted_root = env::var_os(ROOT_VAR)?;
+    let root = dunce::canonicalize(repo_root).ok()?;
+    if Path::new(&hinted_root) != root {
+        return None;
+    }
+    env::var(CHANGE_ID_VAR).ok().filter(|id| !id.is_empty())
+}
@@ -0,0 +1,96 @@
+//! Opt-in JSONL log of computed prompt states
+//!
+//! Each computed prompt appends one line to `history.jsonl` in the state
+//! directory, rotating it once it grows past `max-bytes`:
+//!
+//! ```toml
+//! [history]
+//! enabled = true
+//! max-bytes = 1048576  # rotate past this size (default: 1 MiB)
+//! keep = 3             # rotated files to keep (default: 3)
+//! ```
+
+use crate::config::Config;
+use crate::state;
+use crate::values::SCHEMA_VERSION;
+use chrono::{Local, SecondsFormat};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+const DEFAULT_KEEP: usize = 3;
+
+/// One logged prompt state
+#[derive(Serialize)]
+pub struct Entry<'a> {
+    pub repo: &'a Path,
+    pub change_id: &'a str,
+    pub commit_id: &'a str,
+    pub op_id: &'a str,
+    pub file_count: Option<usize>,
+    pub untracked_count: Option<usize>,
+    pub conflict_count: usize,
+}
+
+/// `entry` with the time it was recorded
+#[derive(Serialize)]
+struct Line<'a> {
+    schema_version: u32,
+    timestamp: String,
+    #[serde(flatten)]
+    entry: &'a Entry<'a>,
+}
+
+/// Whether history is enabled in the config
+pub fn is_enabled(config: &Config) -> bool {
+    config.get("history.enabled").unwrap_or(false)
+}
+
+/// Path of the active history file
+pub fn path(config: &Config) -> Option<PathBuf> {
+    Some(state::base_dir(config)?.join("history.jsonl"))
+}
+
+/// Append `entry`, rotating first if the file is full. Failures are ignored
+/// so logging never breaks the prompt.
+pub fn record(config: &Config, entry: &Entry) {
+    let Some(path) = path(config) else {
+        return;
+    };
+    let Ok(mut line) = serde_json::to_string(&Line {
+        schema_version: SCHEMA_VERSION,
+        timestamp: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+        entry,
+    }) else {
+        return;
+    };
+    line.push('\n');
+
+    let max_bytes = config.get("history.max-bytes").unwrap_or(DEFAULT_MAX_BYTES);
+    if fs::metadata(&path).is_ok_and(|m| m.len() + line.len() as u64 > max_bytes) {
+        rotate(&path, config.get("history.keep").unwrap_or(DEFAULT_KEEP));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).ok();
+    }
+    // A single write of one line keeps concurrent appends from interleaving
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
+        file.write_all(line.as_bytes()).ok();
+    }
+}
+
+/// Shift `history.jsonl` to `.1`, `.1` to `.2`, ..., dropping the oldest
+fn rotate(path: &Path, keep: usize) {
+    let rotated = |n: usize| path.with_extension(format!("jsonl.{n}"));
+    if keep == 0 {
+        fs::remove_file(path).ok();
+        return;
+    }
+    fs::remove_file(rotated(keep)).ok();
+    for n in (1..keep).rev() {
+        fs::rename(rotated(n), rotated(n + 1)).ok();
+    }
+    fs::rename(path, rotated(1)).ok();
+}
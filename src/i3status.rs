@@ -0,0 +1,26 @@
+//! JSON for an i3status-rs custom block
+//!
+//! `--output i3status-rs` prints the plain prompt as `text`, the change id as
+//! `short_text` for when the bar is crowded, and a `state` the bar's theme
+//! colors: `Critical` for conflicts, `Warning` when divergent, `Info` with
+//! changes, `Idle` otherwise. Use it with `json = true`.
+
+use crate::values::State;
+use crate::PromptInfo;
+use serde_json::json;
+
+/// `info` and the rendered `text` as a custom block update
+pub fn encode(info: &PromptInfo, text: &str) -> String {
+    let state = match State::of(info) {
+        State::Conflict => "Critical",
+        State::Divergent => "Warning",
+        State::Modified => "Info",
+        State::Clean => "Idle",
+    };
+    json!({
+        "text": text.trim(),
+        "short_text": info.change_id,
+        "state": state,
+    })
+    .to_string()
+}
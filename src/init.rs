@@ -0,0 +1,179 @@
+//! Shell integration snippets printed by `jj-prompt init <shell>`
+//!
+//! Each snippet is meant to be sourced from the shell's startup file and
+//! wires jj-prompt into the prompt without hand-written glue.
+
+use clap::ValueEnum;
+
+/// Shells with an integration snippet
+#[derive(Clone, Copy, ValueEnum)]
+pub enum InitShell {
+    /// `eval "$(jj-prompt init bash)"` in .bashrc, after setting PS1
+    Bash,
+    /// `eval (jj-prompt init elvish | slurp)` in rc.elv
+    Elvish,
+    /// `jj-prompt init fish | source` in config.fish; show it with `jj_prompt`
+    Fish,
+    /// `jj-prompt init nu | save -f ~/.cache/jj-prompt.nu`, then `source` it
+    Nu,
+    /// `jj-prompt init powershell | Out-String | Invoke-Expression` in $PROFILE
+    Powershell,
+    /// `eval "$(jj-prompt init zsh)"` in .zshrc; show it with `$_jj_prompt_value`
+    Zsh,
+}
+
+const NU: &str = r#"# jj-prompt: the prompt's values as a record, or null outside a repo
+def jj-prompt-record [] {
+    let out = (do --ignore-errors { ^jj-prompt --color always --output nu } | complete)
+    # Exit 3 is a prompt with parts left out, still worth showing
+    if not ($out.exit_code in [0 3]) or ($out.stdout | is-empty) {
+        return null
+    }
+    $out.stdout | from json
+}
+
+$env.PROMPT_COMMAND = {||
+    let dir = ($env.PWD | path basename)
+    let jj = (jj-prompt-record)
+    if $jj == null { $dir } else { $"($dir)($jj.prompt)" }
+}
+"#;
+
+const BASH: &str = r#"# jj-prompt: re-rendered only when `jj-prompt changed` sees a new operation
+_jj_prompt_key=''
+_jj_prompt_value=''
+# Ask the terminal once per session, for readable dim text on light themes
+export JJ_PROMPT_BACKGROUND=${JJ_PROMPT_BACKGROUND:-$(jj-prompt background 2>/dev/null)}
+# PS1 is rebuilt each time so the \[ \] markers in the prompt are decoded;
+# `--escape bash` keeps the text itself from being expanded
+_jj_prompt_ps1=${_jj_prompt_ps1-$PS1}
+
+_jj_prompt_command() {
+  local key
+  if key=$(jj-prompt changed --since "$_jj_prompt_key" 2>/dev/null); then
+    _jj_prompt_key=$key
+    _jj_prompt_value=$(jj-prompt --color always --escape bash 2>/dev/null)
+  fi
+  PS1="${_jj_prompt_value:+$_jj_prompt_value }$_jj_prompt_ps1"
+}
+
+if [[ $PROMPT_COMMAND != *_jj_prompt_command* ]]; then
+  PROMPT_COMMAND="_jj_prompt_command${PROMPT_COMMAND:+; $PROMPT_COMMAND}"
+fi
+"#;
+
+const ELVISH: &str = r#"# jj-prompt: the jj status in front of the existing prompt
+var jj-prompt-previous = $edit:prompt
+set edit:prompt = {
+  # Fails quietly outside a repo, where jj-prompt prints nothing. Other
+  # failures may still print a prompt (exit 3, parts left out).
+  try {
+    var spans = (try { jj-prompt --color always --output elvish 2>$os:dev-null } catch { } | from-json)
+    for span $spans { styled $span[0] (all $span[1]) }
+    put ' '
+  } catch { }
+  $jj-prompt-previous
+}
+"#;
+
+const FISH: &str = r#"# jj-prompt: rendered in the background, shown from the last result meanwhile
+set -g __jj_prompt_file (command mktemp -t jj-prompt.XXXXXX)
+set -g __jj_prompt_value ''
+set -g __jj_prompt_pwd ''
+set -g __jj_prompt_repainting 0
+# Ask the terminal once per session, for readable dim text on light themes
+set -q JJ_PROMPT_BACKGROUND; or set -gx JJ_PROMPT_BACKGROUND (jj-prompt background 2>/dev/null)
+
+# Call from fish_prompt / fish_right_prompt
+function jj_prompt
+    printf '%s' $__jj_prompt_value
+end
+
+function __jj_prompt_render --on-event fish_prompt
+    # Our own repaint shows the fresh value, it doesn't start another render
+    if test "$__jj_prompt_repainting" = 1
+        set -g __jj_prompt_repainting 0
+        return
+    end
+    # A result from another directory would be misleading until replaced
+    if test "$__jj_prompt_pwd" != "$PWD"
+        set -g __jj_prompt_value ''
+        set -g __jj_prompt_pwd $PWD
+    end
+    command sh -c 'jj-prompt --color always --write-to "$1"; kill -USR1 "$2"' sh $__jj_prompt_file $fish_pid >/dev/null 2>&1 &
+    disown 2>/dev/null
+end
+
+function __jj_prompt_update --on-signal SIGUSR1
+    set -l value (string collect < $__jj_prompt_file)
+    # Repaint only on change, so a repaint's own render ends the cycle
+    test "$value" = "$__jj_prompt_value"; and return
+    set -g __jj_prompt_value $value
+    # Cleared by the render the repaint triggers, which it skips
+    set -g __jj_prompt_repainting 1
+    commandline -f repaint
+end
+
+function __jj_prompt_cleanup --on-event fish_exit
+    command rm -f $__jj_prompt_file
+end
+"#;
+
+const POWERSHELL: &str = r#"# jj-prompt: put the jj status in front of the existing prompt
+# Only wrap the original prompt, even if this runs twice
+if (-not $global:JjPromptPrevious) { $global:JjPromptPrevious = $function:prompt }
+function global:prompt {
+    $exitCode = $global:LASTEXITCODE
+    # Decode the glyphs as UTF-8 whatever the console code page is
+    $encoding = [Console]::OutputEncoding
+    [Console]::OutputEncoding = [Text.Encoding]::UTF8
+    try { $jj = (& jj-prompt --color always 2>$null) -join '' } catch { $jj = '' }
+    [Console]::OutputEncoding = $encoding
+    $global:LASTEXITCODE = $exitCode
+    $rest = & $global:JjPromptPrevious
+    if ($jj) { "$jj $rest" } else { $rest }
+}
+"#;
+
+const ZSH: &str = r#"# jj-prompt: rendered in the background, shown from the last result meanwhile
+typeset -g _jj_prompt_file=$(mktemp -t jj-prompt.XXXXXX)
+typeset -g _jj_prompt_value='' _jj_prompt_pwd=''
+# Ask the terminal once per session, for readable dim text on light themes
+export JJ_PROMPT_BACKGROUND=${JJ_PROMPT_BACKGROUND:-$(jj-prompt background 2>/dev/null)}
+# Expands $_jj_prompt_value in PROMPT / RPROMPT on each redraw
+setopt prompt_subst
+
+_jj_prompt_precmd() {
+  # A result from another directory would be misleading until replaced
+  if [[ $_jj_prompt_pwd != $PWD ]]; then
+    _jj_prompt_value=''
+    _jj_prompt_pwd=$PWD
+  fi
+  ( jj-prompt --color always --escape zsh --write-to $_jj_prompt_file 2>/dev/null; kill -USR1 $$ ) &!
+}
+
+TRAPUSR1() {
+  local value=$(<$_jj_prompt_file)
+  [[ $value == $_jj_prompt_value ]] && return
+  _jj_prompt_value=$value
+  zle && zle reset-prompt
+}
+
+_jj_prompt_cleanup() { rm -f $_jj_prompt_file }
+
+autoload -Uz add-zsh-hook
+add-zsh-hook precmd _jj_prompt_precmd
+add-zsh-hook zshexit _jj_prompt_cleanup
+"#;
+
+/// The integration snippet for `shell`
+pub fn script(shell: InitShell) -> &'static str {
+    match shell {
+        InitShell::Bash => BASH,
+        InitShell::Elvish => ELVISH,
+        InitShell::Fish => FISH,
+        InitShell::Nu => NU,
+        InitShell::Powershell => POWERSHELL,
+        InitShell::Zsh => ZSH,
+    }
+}
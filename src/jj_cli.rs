@@ -0,0 +1,173 @@
+//! Running the `jj` executable, and the `--jj-cli` prompt built from it
+//!
+//! With `--jj-cli` (or `jj-cli = true`), the prompt comes from one
+//! `jj log -T` with a template that prints JSON, instead of from jj-lib
+//! reading the repo. That works with any jj whose template language has
+//! `json()`, even after a release changes the on-disk format this build of
+//! jj-lib understands, at the cost of a process spawn. It shows the change id
+//! and its shortest unique prefix, bookmarks, description, conflicts,
+//! divergence, merges, dirtiness, the file count, and the commit's age.
+//!
+//! Everything else comes from jj-lib and is left at its default here:
+//! plugins, topic, ticket, the WIP prefix, `description-template`, `[urls]`
+//! links, stack depth, behind-trunk, private commits, stack conflicts, stale
+//! bookmarks, counters, signatures, the author check, untracked files, and
+//! the `@-` fallback for an empty `@`. jj-lib is still linked either way.
+
+use crate::bookmarks;
+use crate::config::{expand_home, Config};
+use crate::debug::{self, Traced};
+use crate::process;
+use crate::text::sanitize;
+use crate::{Cli, PromptInfo};
+use chrono::DateTime;
+use serde::Deserialize;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
+
+/// The jj to shell out to: `--jj-bin`, `$JJ_PROMPT_JJ_BIN`, `jj-bin` in the
+/// config, else `jj` from PATH
+pub fn bin(cli: &Cli, config: &Config) -> PathBuf {
+    cli.jj_bin
+        .clone()
+        .or_else(|| env::var_os("JJ_PROMPT_JJ_BIN").map(PathBuf::from))
+        .or_else(|| config.get::<String>("jj-bin").map(|bin| expand_home(&bin)))
+        .unwrap_or_else(|| PathBuf::from("jj"))
+}
+
+/// Run `jj args...` in `repo_root` and return its stdout, or `None` if it
+/// fails or is still running at `deadline` (it's killed then). `step` names
+/// the command for `--debug`.
+pub fn run(
+    jj_bin: &Path,
+    repo_root: &Path,
+    args: &[&str],
+    deadline: Option<Instant>,
+    step: &str,
+) -> Option<String> {
+    let mut command = Command::new(jj_bin);
+    command.args(args).current_dir(repo_root);
+    process::output(command, deadline, step)
+}
+
+/// What the template prints for the commit
+#[derive(Deserialize)]
+struct Fields {
+    change_id: String,
+    prefix: String,
+    bookmarks: Vec<String>,
+    description: String,
+    conflict: bool,
+    conflict_count: usize,
+    divergent: bool,
+    /// Parent count of each parent
+    parents: Vec<usize>,
+    empty: bool,
+    timestamp: String,
+    /// Left out of the template with `--no-file-count`
+    files: Option<usize>,
+}
+
+/// Gather the prompt for `revision` (default `@`) by running jj
+pub fn gather(
+    jj_bin: &Path,
+    repo_root: &Path,
+    cli: &Cli,
+    deadline: Option<Instant>,
+) -> Option<PromptInfo> {
+    let revision = cli.revision.as_deref().unwrap_or("@");
+    let files = if cli.no_file_count {
+        ""
+    } else {
+        r#" ++ ",\"files\":" ++ json(self.diff().files().len())"#
+    };
+    let template = format!(
+        r#""{{\"change_id\":" ++ json(change_id)
+        ++ ",\"prefix\":" ++ json(change_id.shortest().prefix())
+        ++ ",\"bookmarks\":[" ++ local_bookmarks.map(|b| json(b.name())).join(",") ++ "]"
+        ++ ",\"description\":" ++ json(description.first_line())
+        ++ ",\"conflict\":" ++ json(conflict)
+        ++ ",\"conflict_count\":" ++ if(conflict, json(self.files().filter(|f| f.file_type() == "conflict").len()), "0")
+        ++ ",\"divergent\":" ++ json(divergent)
+        ++ ",\"parents\":[" ++ parents.map(|p| json(p.parents().len())).join(",") ++ "]"
+        ++ ",\"empty\":" ++ json(empty)
+        ++ ",\"timestamp\":" ++ json(committer.timestamp()){files}
+        ++ "}}\n""#
+    );
+    let mut args = vec!["log", "--no-graph", "-r", revision, "-T", &template];
+    // Let jj snapshot first, like `--snapshot` does with jj-lib
+    if !(cli.snapshot && cli.revision.is_none() && cli.at_operation.is_none()) {
+        args.push("--ignore-working-copy");
+    }
+    if let Some(op) = &cli.at_operation {
+        args.extend(["--at-operation", op]);
+    }
+    let stdout = run(jj_bin, repo_root, &args, deadline, "run jj log")?;
+    let [line] = stdout.lines().collect::<Vec<_>>()[..] else {
+        debug::log("run jj log", "the revision isn't one commit");
+        return None;
+    };
+    let fields: Fields = serde_json::from_str(line).traced("read jj log")?;
+
+    // Only divergent commits pay for a second run to count them
+    let divergent_count = if fields.divergent && !cli.no_disambiguate {
+        let revset = format!("change_id({})", fields.change_id);
+        let args = [
+            "log",
+            "--no-graph",
+            "--ignore-working-copy",
+            "-r",
+            &revset,
+            "-T",
+            r#""\n""#,
+        ];
+        run(jj_bin, repo_root, &args, deadline, "run jj log")
+            .map(|stdout| stdout.lines().count())
+            .filter(|&count| count > 1)
+    } else {
+        None
+    };
+
+    let mut change_id = fields.change_id;
+    change_id.truncate(cli.id_length);
+    let prefix_len = if cli.no_disambiguate {
+        change_id.len()
+    } else {
+        fields.prefix.len().min(change_id.len())
+    };
+    let mut bookmarks: Vec<String> = fields
+        .bookmarks
+        .iter()
+        .map(|name| sanitize(name).into_owned())
+        .collect();
+    if cli.bookmarks_max.is_some() || cli.max_width.is_some() {
+        bookmarks::sort_trunk_last(&mut bookmarks);
+    }
+    let (merge_parent_count, merge_is_parent) = match fields.parents[..] {
+        [] => (None, false),
+        [parent_count] => ((parent_count > 1).then_some(parent_count), parent_count > 1),
+        ref parents => (Some(parents.len()), false),
+    };
+    let age_secs = DateTime::parse_from_rfc3339(&fields.timestamp).map_or(0, |committed| {
+        (chrono::Utc::now() - committed.to_utc()).num_seconds()
+    });
+
+    Some(PromptInfo {
+        change_id,
+        prefix_len,
+        bookmark_urls: vec![None; bookmarks.len()],
+        bookmarks,
+        description: sanitize(fields.description.trim()).into_owned(),
+        has_conflict: fields.conflict,
+        conflict_count: fields.conflict_count,
+        merge_parent_count,
+        merge_is_parent,
+        divergent_count,
+        dirty: cli.dirty && !fields.empty,
+        file_count: fields.files.filter(|&n| n > 0),
+        age_secs,
+        ..PromptInfo::default()
+    })
+}
@@ -0,0 +1,2040 @@
+//! jj-prompt - Fast JJ prompt for starship
+//!
+//! Output format: ` {change_id} {bookmarks} {topic} {ticket} {merge} {⇡N} {⇣N} {⊘N} {status} {sig} {✉} {⚠N} {✂N} {*} {~file_count} {?untracked} {age} {description} {parent}`
+//! Matches jj's native coloring exactly.
+
+mod bookmarks;
+mod budget;
+mod changed;
+mod coalesce;
+mod completions;
+mod config;
+mod console;
+mod counters;
+mod debug;
+mod demo;
+mod detect;
+mod fast;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod forge;
+mod hints;
+mod history;
+mod i3status;
+mod init;
+mod jj_cli;
+mod man;
+mod metadata;
+mod nu;
+mod nvim;
+mod oh_my_posh;
+mod p10k;
+mod parent;
+mod plugins;
+mod polybar;
+mod power;
+mod prefix_cache;
+mod process;
+mod profile;
+mod revalidate;
+mod revsets;
+mod segment;
+mod state;
+mod style;
+#[cfg(feature = "templates")]
+mod templates;
+mod text;
+mod theme;
+mod thresholds;
+mod trace;
+mod unchanged;
+mod values;
+mod waybar;
+mod working_copy;
+
+use budget::{Budget, Part};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use completions::Candidates;
+use config::Config;
+use debug::Traced;
+use demo::DemoState;
+use detect::RepoKind;
+use forge::{RemoteTemplates, UrlKind, UrlVars};
+use hints::HintShell;
+use init::InitShell;
+use jj_lib::hex_util::encode_reverse_hex;
+use jj_lib::object_id::ObjectId;
+use jj_lib::op_walk;
+use jj_lib::repo::{ReadonlyRepo, Repo, StoreFactories};
+use jj_lib::settings::UserSettings;
+use jj_lib::signing::SigStatus;
+use jj_lib::workspace::{default_working_copy_factories, Workspace};
+use metadata::DescriptionSource;
+use parent::{ParentFallback, ParentInfo};
+use revsets::RevsetEvaluator;
+use segment::{Escape, Output, Prompt, Role, Segment, SegmentKind, Span};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use style::{ColorChoice, IconSet, Side, Style};
+use text::{bidi_isolate, sanitize, set_title, truncate_width, BidiMode};
+use theme::{Background, Theme};
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Parser)]
+#[command(name = "jj-prompt")]
+#[command(version, about)]
+// The last of a repeated flag wins, as when a refresh adds `--color`
+#[command(args_override_self = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Override working directory
+    #[arg(long)]
+    cwd: Option<PathBuf>,
+
+    /// Look for the repo above the physical working directory, with
+    /// symlinks resolved, instead of the shell's $PWD
+    #[arg(long)]
+    physical: bool,
+
+    /// Render the prompt for the commit this revset names instead of the
+    /// working copy (e.g. `trunk()`, `@-`, a change id)
+    #[arg(long, short = 'r', value_name = "REVSET")]
+    revision: Option<String>,
+
+    /// Load the repo at this operation rather than the latest one, like jj's
+    /// `--at-operation` (an operation id prefix, or e.g. `@-`)
+    #[arg(long, visible_alias = "at-op", value_name = "OPERATION")]
+    at_operation: Option<String>,
+
+    /// Length of change_id to display (default: 4)
+    #[arg(long, default_value = "4")]
+    id_length: usize,
+
+    /// Show the change id in one color, without the change id index lookup
+    /// for its unique prefix (also skips the divergence check)
+    #[arg(long)]
+    no_disambiguate: bool,
+
+    /// Symbol prefix (default: "  ")
+    #[arg(long)]
+    symbol: Option<String>,
+
+    /// Icon preset for the symbol and indicator glyphs
+    #[arg(long, value_enum)]
+    icons: Option<IconSet>,
+
+    /// Use only plain ASCII glyphs and symbol
+    #[arg(long)]
+    ascii: bool,
+
+    /// Color theme (default: jj-native)
+    #[arg(long, value_enum)]
+    theme: Option<Theme>,
+
+    /// Terminal background, for readable dim text (default: from
+    /// JJ_PROMPT_BACKGROUND or COLORFGBG, else dark)
+    #[arg(long, value_enum)]
+    background: Option<Background>,
+
+    /// When to color the prompt: auto honors NO_COLOR and CLICOLOR_FORCE,
+    /// then colors if a terminal is attached or starship is rendering
+    #[arg(long, value_enum, default_value_t, global = true)]
+    color: ColorChoice,
+
+    /// Same as `--color never`
+    #[arg(long, hide = true)]
+    no_color: bool,
+
+    /// Skip file count (faster)
+    #[arg(long)]
+    no_file_count: bool,
+
+    /// Only the change id, bookmarks, description, and conflict marker, read
+    /// without loading the index (much faster on large repos)
+    #[arg(long)]
+    fast: bool,
+
+    /// Gather the prompt by running `jj log` instead of reading the repo
+    /// with jj-lib, for jj versions this build can't read. Segments that
+    /// need jj-lib (stack, topic, ticket, signature, ...) are left out
+    #[arg(long)]
+    jj_cli: bool,
+
+    /// Render the prompt with a jj commit template instead of the built-in
+    /// segments, e.g. 'change_id.shortest() ++ " " ++ bookmarks'
+    #[cfg(feature = "templates")]
+    #[arg(long, value_name = "TEMPLATE")]
+    template: Option<String>,
+
+    /// Snapshot the working copy first, so the prompt includes edits made
+    /// since the last jj command (slower, and records an operation)
+    #[arg(long)]
+    snapshot: bool,
+
+    /// Drop optional segments still missing after this many milliseconds
+    #[arg(long, value_name = "MS")]
+    timeout_ms: Option<u64>,
+
+    /// Mark a commit with changes with `*` (compares trees, no diff)
+    #[arg(long)]
+    dirty: bool,
+
+    /// Show count of new files the next snapshot will start tracking
+    #[arg(long)]
+    untracked: bool,
+
+    /// Warn when other mutable commits in the current stack have conflicts
+    #[arg(long)]
+    stack_conflicts: bool,
+
+    /// Show how many mutable commits sit between trunk() and @
+    #[arg(long)]
+    stack_depth: bool,
+
+    /// Suggest a rebase when trunk() has commits that @ is missing
+    #[arg(long)]
+    behind_trunk: bool,
+
+    /// Hint at local bookmarks that are merged into trunk and safe to delete
+    #[arg(long)]
+    stale_bookmarks: bool,
+
+    /// Show the working-copy commit's age (from its committer timestamp)
+    #[arg(long)]
+    age: bool,
+
+    /// Only show the age once the commit is older than this many hours
+    /// (default: `thresholds.age` in the config, else 0)
+    #[arg(long)]
+    age_threshold: Option<u64>,
+
+    /// Skip expensive segments while on battery (needs the `battery` feature)
+    #[arg(long)]
+    low_power: bool,
+
+    /// Don't warn when the commit's author differs from the configured jj user
+    #[arg(long)]
+    no_author_check: bool,
+
+    /// Show the parent count next to the merge glyph
+    #[arg(long)]
+    merge_parent_count: bool,
+
+    /// Show whether the working-copy commit is signed and verifies
+    #[arg(long)]
+    signature: bool,
+
+    /// Mark the prompt with `⏱` when computing it took longer than this many
+    /// milliseconds
+    #[arg(long, value_name = "MS")]
+    warn_slow_ms: Option<u64>,
+
+    /// Show the milliseconds next to the slow-prompt glyph
+    #[arg(long)]
+    show_slow_ms: bool,
+
+    /// Reuse an identical render already in flight, waiting up to N ms (0: off)
+    #[arg(long)]
+    coalesce_ms: Option<u64>,
+
+    /// Print the last prompt rendered here at once and refresh it in the
+    /// background for next time
+    #[arg(long)]
+    stale_while_revalidate: bool,
+
+    /// Also set the terminal title to `repo:change_id description` (OSC 2)
+    #[arg(long)]
+    set_title: bool,
+
+    /// Print a short hash of the prompt instead of the prompt, to tell
+    /// cheaply whether it changed
+    #[arg(long)]
+    hash: bool,
+
+    /// Print nothing and exit with 5 if the prompt is the one printed last
+    /// time in this repo
+    #[arg(long)]
+    if_changed: bool,
+
+    /// Replace this file with the prompt instead of printing it (emptied
+    /// outside a repo), for asynchronous shell integrations
+    #[arg(long, value_name = "PATH")]
+    write_to: Option<PathBuf>,
+
+    /// String placed between segments (default: a single space)
+    #[arg(long)]
+    separator: Option<String>,
+
+    /// Glyph for conflicts (default: `>`)
+    #[arg(long)]
+    conflict_glyph: Option<String>,
+
+    /// Glyph for divergent changes (default: `\`)
+    #[arg(long)]
+    divergent_glyph: Option<String>,
+
+    /// Show at most N bookmarks, collapsing the rest into `+M`
+    #[arg(long)]
+    bookmarks_max: Option<usize>,
+
+    /// Color encoding: ANSI escapes, powerline blocks, tmux or zellij styles,
+    /// or plain text for oh-my-posh (JSON) and p10k (with a state line)
+    #[arg(long, value_enum, default_value_t)]
+    output: Output,
+
+    /// Mark escape codes as zero-width for use directly in PS1
+    #[arg(long, value_enum, default_value_t)]
+    escape: Escape,
+
+    /// Which side of the command line the prompt is for
+    #[arg(long, value_enum, default_value_t)]
+    side: Side,
+
+    /// Keep the prompt within N columns, shortening the least important parts
+    #[arg(long)]
+    max_width: Option<usize>,
+
+    /// Truncate the description to N columns, ending in `…`
+    #[arg(long)]
+    description_length: Option<usize>,
+
+    /// Isolate descriptions and bookmark names with Unicode bidi controls
+    #[arg(long, value_enum, default_value_t)]
+    bidi: BidiMode,
+
+    /// Explain on stderr why the prompt, or a part of it, is missing
+    #[arg(long)]
+    debug: bool,
+
+    /// Print how long each phase of the prompt took on stderr
+    #[arg(long)]
+    profile: bool,
+
+    /// jj executable to run for the file count (default:
+    /// $JJ_PROMPT_JJ_BIN, `jj-bin` in the config, else `jj` from PATH)
+    #[arg(long, value_name = "PATH")]
+    jj_bin: Option<PathBuf>,
+
+    /// Append timestamped tracing events for each run to this file
+    /// (default: $JJ_PROMPT_LOG)
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// When @ is empty with no description, also show @-'s bookmarks and
+    /// description, or show @- instead, marked as the parent
+    #[arg(long, value_enum)]
+    parent_fallback: Option<ParentFallback>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Output prompt string (default)
+    Prompt,
+    /// Exit 0 if in jj repo, 1 otherwise
+    Detect {
+        /// Print the workspace root
+        #[arg(long)]
+        print_root: bool,
+
+        /// Print `jj`, `colocated` (jj and git share the working tree), or
+        /// `none`
+        #[arg(long)]
+        print_kind: bool,
+
+        /// Print the root, kind, workspace name, backend, and whether the
+        /// working copy is stale as JSON
+        #[arg(long, conflicts_with_all = ["print_root", "print_kind"])]
+        json: bool,
+    },
+    /// Read directories from stdin, one per line (or NUL-separated), and
+    /// print one prompt per line, empty outside a repo
+    Batch,
+    /// Print the forge URL for the working-copy commit from `[urls]` config
+    Url {
+        /// What to link to
+        #[arg(value_enum, default_value_t)]
+        kind: UrlKind,
+
+        /// Bookmark to link (default: first bookmark on the working copy)
+        #[arg(long)]
+        bookmark: Option<String>,
+    },
+    /// Inspect or clear this repo's cached state
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+    /// Print the man page, or write it and one per subcommand to a directory
+    Man {
+        /// Directory to write `jj-prompt.1` and `jj-prompt-<command>.1` to
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Render scripted prompt states with fake ids, no repo needed
+    Demo {
+        /// States to render, in order (default: all)
+        #[arg(value_enum)]
+        states: Vec<DemoState>,
+
+        /// Pause between states, for terminal recordings
+        #[arg(long, default_value = "0")]
+        delay_ms: u64,
+    },
+    /// Print shell exports for the fallback prompt (eval from a precmd hook)
+    Hints {
+        /// Syntax of the exports
+        #[arg(long, value_enum, default_value_t)]
+        shell: HintShell,
+    },
+    /// Print a key for the repo's current operation; exit 0 if it differs
+    /// from `--since`
+    Changed {
+        /// Key printed by a previous call
+        #[arg(long, default_value = "")]
+        since: String,
+    },
+    /// Ask the terminal whether its background is light or dark (OSC 11)
+    /// and print `light` or `dark`, for exporting as JJ_PROMPT_BACKGROUND
+    Background {
+        /// How long to wait for the terminal's answer
+        #[arg(long, default_value = "100")]
+        timeout_ms: u64,
+    },
+    /// Print a snippet that hooks jj-prompt into a shell's prompt
+    Init {
+        /// Shell to integrate with
+        #[arg(value_enum)]
+        shell: InitShell,
+    },
+    /// List repo values for shell completion, one per line
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// Kind of value to list
+        kind: Candidates,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Print the repo's state directory
+    Path,
+    /// Delete the repo's state directory
+    Clear,
+}
+
+/// Exit status of a prompt, so shell integrations can tell failures apart
+#[derive(Clone, Copy)]
+enum Status {
+    Success = 0,
+    /// Not inside a jj workspace, e.g. to fall back to a git prompt
+    NotARepo = 1,
+    /// The repo failed to load (or `--write-to` failed to write)
+    LoadFailed = 2,
+    /// Printed, but with parts dropped at `--timeout-ms` or from the
+    /// session hints in place of the repo
+    Degraded = 3,
+    /// Unknown flags or bad values; clap's own usage errors
+    InvalidArgs = 4,
+    /// Nothing printed: the prompt is the one printed last time, with
+    /// `--if-changed`
+    Unchanged = 5,
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+/// The `jj-prompt` command line
+pub fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) => {
+            err.print().ok();
+            // `--help` and `--version` also arrive as errors
+            return if err.use_stderr() {
+                Status::InvalidArgs.into()
+            } else {
+                Status::Success.into()
+            };
+        }
+    };
+    if cli.debug {
+        debug::enable();
+    }
+    if cli.profile {
+        profile::enable();
+    }
+    if let Some(path) = trace::path(cli.log_file.as_deref()) {
+        trace::init(&path);
+    }
+    let started = Instant::now();
+    let cwd = cli
+        .cwd
+        .clone()
+        .or_else(|| (!cli.physical).then(logical_cwd).flatten())
+        .or_else(|| env::current_dir().ok())
+        .unwrap_or_default();
+    let _span = tracing::info_span!(
+        "jj-prompt",
+        cwd = %cwd.display(),
+        args = ?env::args_os().skip(1).collect::<Vec<_>>(),
+    )
+    .entered();
+
+    match cli.command {
+        Some(Command::Detect {
+            print_root,
+            print_kind,
+            json,
+        }) => {
+            let repo_root = find_jj_root(&cwd);
+            if json {
+                let workspace = repo_root.as_deref().and_then(|root| {
+                    load_workspace(root, &config::load_jj_settings(root)?)
+                });
+                println!("{}", detect::json(repo_root.as_deref(), workspace.as_ref()));
+            }
+            if let Some(root) = repo_root.as_deref().filter(|_| print_root) {
+                println!("{}", root.display());
+            }
+            if print_kind {
+                println!("{}", RepoKind::of(repo_root.as_deref()).name());
+            }
+            if repo_root.is_some() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Some(Command::Batch) => {
+            let result = run_batch(&cwd, &cli);
+            profile::report(started.elapsed());
+            match result {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(_) => ExitCode::FAILURE,
+            }
+        }
+        Some(Command::Url { kind, ref bookmark }) => {
+            if let Some(url) = run_url(&cwd, &cli, kind, bookmark.as_deref()) {
+                println!("{url}");
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Some(Command::Cache { ref action }) => {
+            let Some(repo_root) = find_jj_root(&cwd) else {
+                return ExitCode::FAILURE;
+            };
+            let config = Config::load(Some(&repo_root));
+            let Some(dir) = state::repo_dir(&config, &repo_root) else {
+                return ExitCode::FAILURE;
+            };
+            match action {
+                CacheAction::Path => println!("{}", dir.display()),
+                CacheAction::Clear => {
+                    if dir.exists() && fs::remove_dir_all(&dir).is_err() {
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+            ExitCode::SUCCESS
+        }
+        Some(Command::Completions { shell }) => {
+            completions::generate(shell, &mut Cli::command());
+            ExitCode::SUCCESS
+        }
+        Some(Command::Man { ref dir }) => {
+            match man::generate(Cli::command(), dir.as_deref()).traced("write man page") {
+                Some(()) => ExitCode::SUCCESS,
+                None => ExitCode::FAILURE,
+            }
+        }
+        Some(Command::Demo {
+            ref states,
+            delay_ms,
+        }) => {
+            let style = Style::resolve(&cli, &Config::load(None));
+            let states = if states.is_empty() {
+                DemoState::value_variants()
+            } else {
+                states.as_slice()
+            };
+            for (i, state) in states.iter().enumerate() {
+                if i > 0 {
+                    thread::sleep(Duration::from_millis(delay_ms));
+                }
+                println!("{}", render_prompt(&state.info(), &cli, &style));
+            }
+            ExitCode::SUCCESS
+        }
+        Some(Command::Hints { shell }) => {
+            if let Some(exports) = run_hints(&cwd, shell) {
+                print!("{exports}");
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Some(Command::Changed { ref since }) => {
+            let key = changed::key(find_jj_root(&cwd).as_deref());
+            println!("{key}");
+            if key != *since {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Some(Command::Background { timeout_ms }) => {
+            let background = console::query_background(Duration::from_millis(timeout_ms))
+                .or_else(console::background_hint);
+            match background {
+                Some(background) => {
+                    println!("{}", background.name());
+                    ExitCode::SUCCESS
+                }
+                None => ExitCode::FAILURE,
+            }
+        }
+        Some(Command::Init { shell }) => {
+            print!("{}", init::script(shell));
+            ExitCode::SUCCESS
+        }
+        Some(Command::Complete { kind }) => {
+            let repo = find_jj_root(&cwd).and_then(|root| {
+                let settings = config::load_jj_settings(&root)?;
+                load_repo(&root, &settings, None)
+            });
+            let Some((_, repo)) = repo else {
+                return ExitCode::FAILURE;
+            };
+            for candidate in completions::list_candidates(repo.as_ref(), kind) {
+                println!("{candidate}");
+            }
+            ExitCode::SUCCESS
+        }
+        Some(Command::Prompt) | None => {
+            let (output, status) = run_prompt(&cwd, &cli);
+            profile::report(started.elapsed());
+            tracing::info!(status = status as u8, "prompt");
+            if matches!(status, Status::Unchanged) {
+                // The last prompt, printed or written, still stands
+            } else if let Some(path) = &cli.write_to {
+                // Readers must never see a stale prompt for another repo
+                if write_replacing(path, output.as_deref().unwrap_or_default()).is_err() {
+                    return Status::LoadFailed.into();
+                }
+            } else if let Some(output) = &output {
+                print!("{output}");
+            }
+            status.into()
+        }
+    }
+}
+
+/// Render a prompt for each directory on stdin. Directories in the same
+/// workspace share one render.
+fn run_batch(cwd: &Path, cli: &Cli) -> io::Result<()> {
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+    let separator = if input.contains(&0) { b'\0' } else { b'\n' };
+
+    let mut rendered: HashMap<PathBuf, Option<String>> = HashMap::new();
+    let mut stdout = io::stdout().lock();
+    for line in input.split(|&b| b == separator) {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            continue;
+        }
+        let dir = cwd.join(line);
+        let prompt = match find_jj_root(&dir) {
+            Some(root) => rendered
+                .entry(root)
+                .or_insert_with_key(|root| run_prompt(root, cli).0)
+                .as_deref(),
+            None => None,
+        };
+        writeln!(stdout, "{}", prompt.unwrap_or_default())?;
+    }
+    stdout.flush()
+}
+
+/// Write `contents` to a temporary file next to `path`, then rename it over
+/// `path` so readers see either the old or the new contents, never a mix
+fn write_replacing(path: &Path, contents: &str) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        fs::remove_file(&tmp).ok();
+    })
+}
+
+/// The shell's `$PWD`, if it is the current directory. Like git, this keeps
+/// the symlinks the user came through, so a repo above a linked directory is
+/// found from inside it.
+fn logical_cwd() -> Option<PathBuf> {
+    let pwd = PathBuf::from(env::var_os("PWD")?);
+    if !pwd.is_absolute() {
+        return None;
+    }
+    // Stale when the shell didn't export it, e.g. after a bare chdir
+    let physical = dunce::canonicalize(env::current_dir().ok()?).ok()?;
+    (dunce::canonicalize(&pwd).ok()? == physical).then_some(pwd)
+}
+
+/// Walk up directory tree to find .jj
+///
+/// `start` is made absolute first, so a relative `--cwd` still reaches the
+/// directories above it. The walk ends at the root, drive (`C:\`), or UNC
+/// share (`\\server\share`), and the root found is given without the `\\?\`
+/// verbatim prefix, as jj-lib spells workspace roots.
+fn find_jj_root(start: &Path) -> Option<PathBuf> {
+    let start = std::path::absolute(start).ok()?;
+    start
+        .ancestors()
+        .find(|dir| dir.join(".jj").is_dir())
+        .map(|root| dunce::simplified(root).to_path_buf())
+}
+
+/// Get file count by shelling out to jj (the tree diff API is complex). The
+/// diff is killed if it's still running at `deadline`.
+fn get_file_count(
+    jj_bin: &Path,
+    repo_root: &Path,
+    revision: &str,
+    op_id: Option<&str>,
+    deadline: Option<Instant>,
+) -> Option<usize> {
+    let mut args = vec!["diff", "--stat", "--ignore-working-copy", "-r", revision];
+    if let Some(op_id) = op_id {
+        args.extend(["--at-operation", op_id]);
+    }
+    let stdout = jj_cli::run(jj_bin, repo_root, &args, deadline, "run jj diff")?;
+    // Last line looks like: "9 files changed, 449 insertions(+), 187 deletions(-)"
+    // Or just a single file: "1 file changed, 10 insertions(+)"
+    let last_line = stdout.lines().last()?;
+
+    // Extract the first number (file count)
+    last_line
+        .split_whitespace()
+        .next()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|&n| n > 0)
+}
+
+/// Everything gathered from the repo for a single prompt render
+#[derive(Default)]
+struct PromptInfo {
+    /// Change ID truncated to the requested length
+    change_id: String,
+    /// Length of the shortest unique prefix within `change_id`
+    prefix_len: usize,
+    /// Output of each `[plugins]` command, in the order they're declared
+    plugins: Vec<plugins::PluginOutput>,
+    bookmarks: Vec<String>,
+    /// Forge URL of each bookmark, from `urls.bookmark`
+    bookmark_urls: Vec<Option<String>>,
+    /// Forge URL of the change, from `urls.change` or `urls.commit`
+    change_url: Option<String>,
+    /// First line of the description
+    description: String,
+    /// Length of a WIP prefix (`wip:`, `fixup!`, ...) starting the description
+    wip_prefix_len: Option<usize>,
+    /// Shown in place of an empty description, with where it came from
+    description_fallback: Option<(DescriptionSource, String)>,
+    /// Topic from the description (`[topic: ...]` or a `Topic:` trailer)
+    topic: Option<String>,
+    /// Ticket id from `ticket-pattern`, with its tracker URL if configured
+    ticket: Option<(String, Option<String>)>,
+    /// Parent count of @ if it's a merge, else of @- if that is
+    merge_parent_count: Option<usize>,
+    /// The merge is @- rather than @ itself
+    merge_is_parent: bool,
+    has_conflict: bool,
+    /// Number of conflicted paths in the commit's tree
+    conflict_count: usize,
+    /// Visible commits sharing the change id, if more than one
+    divergent_count: Option<usize>,
+    /// The commit's tree differs from its parents', with `--dirty`
+    dirty: bool,
+    file_count: Option<usize>,
+    untracked_count: Option<usize>,
+    /// Mutable commits in `trunk()..@`, including @ itself
+    stack_depth: Option<usize>,
+    /// Commits in `@..trunk()`, i.e. trunk has moved on since @ was based
+    behind_trunk: Option<usize>,
+    /// Commits in `trunk()..@` matching the private-commits revset
+    private_count: Option<usize>,
+    /// Conflicted mutable ancestors/descendants, excluding @ itself
+    stack_conflict_count: Option<usize>,
+    /// Local bookmarks that are fully pushed and merged into trunk
+    stale_bookmark_count: Option<usize>,
+    /// Matches of each `[counters]` revset, in the order they're declared
+    counters: Vec<counters::Count>,
+    /// Author email differs from the configured `user.email`
+    author_mismatch: bool,
+    /// Signature status, if the commit is signed and `--signature` is set
+    signature: Option<SigStatus>,
+    /// Seconds since the working-copy commit was last rewritten
+    age_secs: i64,
+    /// Parts were left out when the time budget ran out
+    degraded: bool,
+    /// @- after an empty, undescribed @ (`parent-fallback = "also"`)
+    parent: Option<ParentInfo>,
+    /// The info is for @- in place of an empty @ (`parent-fallback = "instead"`)
+    is_parent: bool,
+    /// The whole prompt from `--template`, with jj's color codes
+    templated: Option<String>,
+    /// Milliseconds the prompt took, if past `--warn-slow-ms`
+    slow_ms: Option<u64>,
+}
+
+/// The prompt for `cwd`, with the exit status describing it
+fn run_prompt(cwd: &Path, cli: &Cli) -> (Option<String>, Status) {
+    let Some(repo_root) = find_jj_root(cwd) else {
+        debug::log(
+            "find repo",
+            format!("no .jj directory above {}", cwd.display()),
+        );
+        return (None, Status::NotARepo);
+    };
+    let _span = tracing::info_span!("repo", root = %repo_root.display()).entered();
+    let status = Cell::new(Status::Success);
+    let output = render_repo(&repo_root, cwd, cli, &status);
+    let status = match (&output, status.get()) {
+        (None, Status::Unchanged) => Status::Unchanged,
+        (None, _) => Status::LoadFailed,
+        (Some(_), status) => status,
+    };
+    (output, status)
+}
+
+/// Render the prompt for the repo at `repo_root`, noting in `status` if
+/// parts were left out, it's the fallback from the session hints, or it's
+/// left out as unchanged
+fn render_repo(repo_root: &Path, cwd: &Path, cli: &Cli, status: &Cell<Status>) -> Option<String> {
+    let started = Instant::now();
+    let (config, settings, style) = profile::time("load config", || {
+        let config = Config::load(Some(repo_root));
+        // Read once, for the colors here and for loading the workspace
+        let settings = config::load_jj_settings(repo_root);
+        let mut style = Style::resolve(cli, &config);
+        if let Some(settings) = &settings {
+            style.apply_jj_colors(settings.config());
+        }
+        (config, settings, style)
+    });
+    let render = || {
+        let mut info = gather_info(repo_root, cli, &config, settings.as_ref())?;
+        if info.degraded {
+            status.set(Status::Degraded);
+        }
+        let warn_slow_ms = cli.warn_slow_ms.or_else(|| config.get("warn-slow-ms"));
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        // Timing would change the hash from one render to the next
+        info.slow_ms = warn_slow_ms
+            .filter(|&ms| elapsed_ms > ms && !cli.hash)
+            .map(|_| elapsed_ms);
+        let prompt = profile::time("render", || render_prompt(&info, cli, &style));
+        match style.output {
+            Output::Ansi | Output::Powerline if cli.set_title => {
+                Some(title_sequence(repo_root, &info, &style) + &prompt)
+            }
+            Output::Polybar => Some(polybar::clickable(&prompt, repo_root, &config)),
+            _ => Some(prompt),
+        }
+    };
+    // If the repo can't be loaded right now, fall back to the session hints,
+    // which only know the current working copy
+    let live = cli.revision.is_none() && cli.at_operation.is_none();
+    let render = || {
+        let prompt = render().or_else(|| {
+            status.set(Status::Degraded);
+            live.then(|| render_fallback(repo_root, cli, &style))
+                .flatten()
+        })?;
+        Some(if cli.hash {
+            format!("{:016x}", state::fnv1a(prompt.as_bytes()))
+        } else {
+            prompt
+        })
+    };
+
+    let Some(dir) = state::repo_dir(&config, repo_root) else {
+        return render();
+    };
+    // The same flags render differently with colors and without
+    let mut args = revalidate::args().join(" ".as_ref()).into_encoded_bytes();
+    args.push(u8::from(style.colored));
+    let key = state::fnv1a(&args);
+
+    // Show the previous render now and the current one next time. The
+    // refresh reruns this command line, which in batch mode has no input.
+    let stale_while_revalidate =
+        cli.stale_while_revalidate || config.get("stale-while-revalidate").unwrap_or(false);
+    let batch = matches!(cli.command, Some(Command::Batch));
+    // Concurrent renders for the same repo and flags share one result
+    let coalesce_ms = cli
+        .coalesce_ms
+        .or_else(|| config.get("coalesce-ms"))
+        .unwrap_or(0);
+    let output = if stale_while_revalidate && live && !batch {
+        revalidate::run(&dir, cwd, key, style.colored, render)
+    } else if coalesce_ms > 0 {
+        coalesce::run(&dir, key, Duration::from_millis(coalesce_ms), render)
+    } else {
+        render()
+    };
+
+    // Only what was shown counts as printed last time, not a refresh
+    if cli.if_changed
+        && !revalidate::is_refresh()
+        && unchanged::check(&dir, key, output.as_deref()?)
+    {
+        status.set(Status::Unchanged);
+        return None;
+    }
+    output
+}
+
+/// Load the workspace containing `cwd` and its repo at the current head, or
+/// at `at_operation` if given
+fn load_repo(
+    repo_root: &Path,
+    settings: &UserSettings,
+    at_operation: Option<&str>,
+) -> Option<(Workspace, Arc<ReadonlyRepo>)> {
+    let workspace = load_workspace(repo_root, settings)?;
+    let repo_loader = workspace.repo_loader();
+    let repo = match at_operation {
+        Some(op_str) => {
+            let op =
+                op_walk::resolve_op_for_load(repo_loader, op_str).traced("resolve operation")?;
+            repo_loader.load_at(&op).traced("load repo")?
+        }
+        None => repo_loader.load_at_head().traced("load repo")?,
+    };
+    Some((workspace, repo))
+}
+
+/// Load the workspace at `repo_root`, without the repo itself
+fn load_workspace(repo_root: &Path, settings: &UserSettings) -> Option<Workspace> {
+    Workspace::load(
+        settings,
+        repo_root,
+        &StoreFactories::default(),
+        &default_working_copy_factories(),
+    )
+    .traced("load workspace")
+}
+
+fn run_url(cwd: &Path, cli: &Cli, kind: UrlKind, bookmark: Option<&str>) -> Option<String> {
+    let repo_root = find_jj_root(cwd)?;
+    let settings = config::load_jj_settings(&repo_root)?;
+    let (workspace, repo) = load_repo(&repo_root, &settings, cli.at_operation.as_deref())?;
+    let wc_id = repo
+        .view()
+        .wc_commit_ids()
+        .get(workspace.workspace_name())?;
+    let commit = repo.store().get_commit(wc_id).ok()?;
+
+    let bookmarks = repo
+        .view()
+        .local_bookmarks_for_commit(wc_id)
+        .map(|(name, _)| name.as_str().to_string())
+        .collect::<Vec<_>>();
+    let config = Config::load(Some(workspace.workspace_root()));
+    let ticket = metadata::extract_ticket(&config, commit.description(), &bookmarks);
+    let change_id = encode_reverse_hex(commit.change_id().as_bytes());
+    let commit_id = commit.id().hex();
+    let vars = UrlVars {
+        change_id: &change_id,
+        commit_id: &commit_id,
+        bookmark: bookmark.or(bookmarks.first().map(String::as_str)),
+        ticket: ticket.as_deref(),
+    };
+
+    let remote = RemoteTemplates::detect(&config, repo.store());
+    forge::build_url(&config, &remote, kind, &vars)
+}
+
+fn run_hints(cwd: &Path, shell: HintShell) -> Option<String> {
+    let repo_root = find_jj_root(cwd)?;
+    let settings = config::load_jj_settings(&repo_root)?;
+    let (workspace, repo) = load_repo(&repo_root, &settings, None)?;
+    let wc_id = repo
+        .view()
+        .wc_commit_ids()
+        .get(workspace.workspace_name())?;
+    let commit = repo.store().get_commit(wc_id).ok()?;
+    let change_id = encode_reverse_hex(commit.change_id().as_bytes());
+    Some(hints::exports(shell, &repo_root, &change_id))
+}
+
+fn gather_info(
+    repo_root: &Path,
+    cli: &Cli,
+    config: &Config,
+    settings: Option<&UserSettings>,
+) -> Option<PromptInfo> {
+    let start = Instant::now();
+    let timeout_ms = cli.timeout_ms.or_else(|| config.get("timeout-ms"));
+    // Everything from one `jj log`, whatever the repo's format
+    if cli.jj_cli || config.get("jj-cli").unwrap_or(false) {
+        let deadline = Budget::new(start, timeout_ms, config).deadline();
+        let jj_bin = jj_cli::bin(cli, config);
+        return profile::time("jj log", || {
+            jj_cli::gather(&jj_bin, repo_root, cli, deadline)
+        });
+    }
+    let settings = settings?;
+    // Just the change id and bookmarks, without loading the index
+    if cli.fast && cli.revision.is_none() && cli.at_operation.is_none() {
+        let info = profile::time("fast path", || {
+            load_workspace(repo_root, settings).and_then(|ws| fast::gather(&ws, cli))
+        });
+        if let Some(info) = info {
+            return Some(info);
+        }
+    }
+    let (mut workspace, repo) = profile::time("load repo", || {
+        load_repo(repo_root, settings, cli.at_operation.as_deref())
+    })?;
+    // Rendering the present working copy, rather than another commit or the
+    // past, which the files on disk and the history log don't describe
+    let live = cli.revision.is_none() && cli.at_operation.is_none();
+    // Pick up edits made since the last jj command, if asked to
+    let repo = if cli.snapshot && live {
+        profile::time("snapshot", || working_copy::snapshot(&mut workspace, &repo)).unwrap_or(repo)
+    } else {
+        repo
+    };
+    tracing::info!(operation = %repo.op_id().hex(), "loaded repo");
+    let view = repo.view();
+
+    // On battery in low-power mode, skip everything expensive
+    let full = !power::is_active(cli.low_power, config);
+
+    // Get working copy commit, or the single one `--revision` names. `at`
+    // stands for it in revsets.
+    let revsets = RevsetEvaluator::new(&workspace, &repo);
+    let wc_id = match &cli.revision {
+        Some(revision) => match revsets.commit_ids(revision)?.as_slice() {
+            [id] => id.clone(),
+            ids => {
+                debug::log("--revision", format!("{} commits, not one", ids.len()));
+                return None;
+            }
+        },
+        None => view
+            .wc_commit_ids()
+            .get(workspace.workspace_name())
+            .traced("working-copy commit")?
+            .clone(),
+    };
+    let mut commit = repo.store().get_commit(&wc_id).traced("read commit")?;
+
+    // An empty, undescribed @ says little; @- may say more
+    let blank_parent = cli
+        .parent_fallback
+        .or_else(|| config.get("parent-fallback"))
+        .filter(|_| cli.revision.is_none())
+        .and_then(|mode| Some((mode, parent::blank_commit_parent(repo.as_ref(), &commit)?)));
+    let mut parent = None;
+    let mut is_parent = false;
+    match blank_parent {
+        Some((ParentFallback::Also, blank_parent)) => {
+            parent = Some(ParentInfo::new(repo.as_ref(), &blank_parent))
+                .filter(|parent| !parent.is_empty());
+        }
+        Some((ParentFallback::Instead, blank_parent)) => {
+            commit = blank_parent;
+            is_parent = true;
+        }
+        None => {}
+    }
+    let wc_id = commit.id().clone();
+    let at = if cli.revision.is_some() || is_parent {
+        commit.id().hex()
+    } else {
+        "@".to_string()
+    };
+
+    // Change ID (reverse hex format like jj uses)
+    let mut change_id = encode_reverse_hex(commit.change_id().as_bytes());
+    change_id.truncate(cli.id_length);
+
+    // The user's template in place of every segment
+    #[cfg(feature = "templates")]
+    if let Some(template) = cli.template.clone().or_else(|| config.get("template")) {
+        let templated = profile::time("template", || {
+            templates::CommitTemplates::new(&workspace, &repo, &revsets)?
+                .render(&template, &commit, true)
+        })?;
+        return Some(PromptInfo {
+            prefix_len: change_id.len(),
+            change_id,
+            templated: Some(templated),
+            ..PromptInfo::default()
+        });
+    }
+
+    // Optional parts, most important first, until the time budget runs out
+    let budget = Budget::new(start, timeout_ms, config);
+    // jj's default of `none()` matches nothing, so there's nothing to count
+    let private_commits = config
+        .get::<String>("private-commits")
+        .or_else(|| {
+            repo.settings()
+                .config()
+                .get::<String>("git.private-commits")
+                .ok()
+        })
+        .filter(|revset| revset.trim() != "none()");
+    let mut prefix_len = change_id.len();
+    let mut bookmarks = Vec::new();
+    let mut signature = None;
+    let mut stack_depth = None;
+    let mut behind_trunk = None;
+    let mut private_count = None;
+    let mut stack_conflict_count = None;
+    let mut stale_bookmark_count = None;
+    let mut counters = Vec::new();
+    let mut plugin_outputs = Vec::new();
+    let mut untracked_count = None;
+    let mut file_count = None;
+    let mut degraded = false;
+    let mut counter_defs = counters::load(config);
+    let mut plugin_defs = plugins::load(config);
+    for &part in budget.parts() {
+        // Only a part that would have run counts as left out
+        let enabled = match part {
+            Part::Prefix => !cli.no_disambiguate,
+            Part::Bookmarks => true,
+            Part::Signature => cli.signature && commit.is_signed(),
+            Part::StackDepth => full && cli.stack_depth,
+            Part::BehindTrunk => full && cli.behind_trunk,
+            Part::Private => full && private_commits.is_some(),
+            Part::StackConflicts => full && cli.stack_conflicts,
+            Part::StaleBookmarks => full && cli.stale_bookmarks,
+            Part::Counters => full && !counter_defs.is_empty(),
+            Part::Plugins => full && !plugin_defs.is_empty(),
+            Part::Untracked => full && cli.untracked && live,
+            Part::FileCount => full && !cli.no_file_count,
+        };
+        if !enabled {
+            continue;
+        }
+        if budget.is_spent() {
+            degraded = true;
+            break;
+        }
+        let part_started = Instant::now();
+        match part {
+            // Unique prefix length for coloring, cached until the next operation
+            Part::Prefix => {
+                let state_dir = state::repo_dir(config, repo_root);
+                let op_id = repo.op_id().hex();
+                let full_change_id = encode_reverse_hex(commit.change_id().as_bytes());
+                let cached = state_dir
+                    .as_deref()
+                    .and_then(|dir| prefix_cache::lookup(dir, &op_id, &full_change_id));
+                let len = cached.or_else(|| {
+                    let len = repo
+                        .shortest_unique_change_id_prefix_len(commit.change_id())
+                        .ok()?;
+                    if let Some(dir) = &state_dir {
+                        prefix_cache::store(dir, &op_id, &full_change_id, len);
+                    }
+                    Some(len)
+                });
+                if let Some(len) = len {
+                    prefix_len = len.min(change_id.len());
+                }
+            }
+            // Bookmarks on this commit
+            Part::Bookmarks => {
+                bookmarks = view
+                    .local_bookmarks_for_commit(&wc_id)
+                    .map(|(name, _)| name.as_str().to_string())
+                    .collect();
+                if cli.bookmarks_max.is_some() || cli.max_width.is_some() {
+                    bookmarks::sort_trunk_last(&mut bookmarks);
+                }
+            }
+            // Signature verification (optional, may run gpg/ssh-keygen)
+            Part::Signature => {
+                let status = match commit.verification() {
+                    Ok(Some(verification)) => verification.status,
+                    Ok(None) | Err(_) => SigStatus::Unknown,
+                };
+                signature = Some(status);
+            }
+            // Stack height above trunk (optional, evaluates a revset)
+            Part::StackDepth => {
+                stack_depth = revsets
+                    .count(&format!("(trunk()..{at}) & mutable()"))
+                    .filter(|&n| n > 0);
+            }
+            // Trunk commits missing from @ (optional, evaluates a revset)
+            Part::BehindTrunk => {
+                behind_trunk = revsets.count(&format!("{at}..trunk()")).filter(|&n| n > 0);
+            }
+            // Private work that shouldn't be pushed (if a revset is configured)
+            Part::Private => {
+                if let Some(private) = &private_commits {
+                    private_count = revsets
+                        .count(&format!("(trunk()..{at}) & ({private})"))
+                        .filter(|&n| n > 0);
+                }
+            }
+            // Conflicts elsewhere in the stack (optional, evaluates a revset)
+            Part::StackConflicts => {
+                stack_conflict_count = revsets
+                    .count(&format!(
+                        "(mutable() & (::{at} | {at}::) & conflicts()) ~ {at}"
+                    ))
+                    .filter(|&n| n > 0);
+            }
+            // Deletable bookmarks (optional, walks all bookmarks)
+            Part::StaleBookmarks => {
+                stale_bookmark_count = revsets
+                    .commit_ids("trunk()")
+                    .and_then(|ids| ids.into_iter().next())
+                    .map(|trunk| bookmarks::count_stale(repo.as_ref(), &trunk))
+                    .filter(|&n| n > 0);
+            }
+            // User-defined revset counts (if any are configured)
+            Part::Counters => {
+                for (name, counter) in std::mem::take(&mut counter_defs) {
+                    let Some(count) = revsets.count(&counter.revset).filter(|&n| n > 0) else {
+                        continue;
+                    };
+                    counters.push(counters::Count {
+                        name,
+                        icon: counter.icon,
+                        count,
+                    });
+                }
+            }
+            // External commands (if any are configured, each with its timeout)
+            Part::Plugins => {
+                let change_id = encode_reverse_hex(commit.change_id().as_bytes());
+                let commit_id = commit.id().hex();
+                let env = plugins::PluginEnv {
+                    repo_root,
+                    change_id: &change_id,
+                    commit_id: &commit_id,
+                };
+                let plugins = std::mem::take(&mut plugin_defs);
+                plugin_outputs = plugins::run_all(plugins, &env, budget.deadline());
+            }
+            // Untracked files (optional, walks the working copy)
+            Part::Untracked => {
+                untracked_count =
+                    working_copy::count_untracked(&workspace, repo.settings()).filter(|&n| n > 0);
+            }
+            // File count (optional, shells out to jj, stopped at the deadline)
+            Part::FileCount => {
+                let op_id = cli.at_operation.is_some().then(|| repo.op_id().hex());
+                file_count = get_file_count(
+                    &jj_cli::bin(cli, config),
+                    repo_root,
+                    &at,
+                    op_id.as_deref(),
+                    budget.deadline(),
+                );
+                // Stopped at the deadline
+                degraded |= file_count.is_none() && budget.is_spent();
+            }
+        }
+        profile::record(part.name(), part_started.elapsed());
+    }
+
+    // Description (first line)
+    let mut description = commit
+        .description()
+        .lines()
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_string();
+
+    // Topic chip, stripped from the description if it was inline
+    let topic = metadata::extract_topic(config, commit.description()).map(|topic| {
+        if let Some(first_line) = topic.first_line {
+            description = first_line;
+        }
+        topic.name
+    });
+    // The user's own formatting from jj's template language, if configured
+    #[cfg(feature = "templates")]
+    if let Some(template) = config.get::<String>("description-template") {
+        let rendered = profile::time("description template", || {
+            templates::CommitTemplates::new(&workspace, &repo, &revsets)?
+                .render(&template, &commit, false)
+        });
+        if let Some(rendered) = rendered {
+            description = rendered.lines().next().unwrap_or("").trim().to_string();
+        }
+    }
+    // Commit text may hold escape sequences; never pass them to the terminal
+    let description = sanitize(&description).into_owned();
+    let topic = topic.map(|topic| sanitize(&topic).into_owned());
+
+    let full_change_id = encode_reverse_hex(commit.change_id().as_bytes());
+    let commit_id = commit.id().hex();
+    let remote = RemoteTemplates::detect(config, repo.store());
+    let build_url = |kind: UrlKind, bookmark: Option<&str>, ticket: Option<&str>| {
+        let vars = UrlVars {
+            change_id: &full_change_id,
+            commit_id: &commit_id,
+            bookmark,
+            ticket,
+        };
+        forge::build_url(config, &remote, kind, &vars)
+    };
+
+    // Ticket id, linked to the tracker if `urls.ticket` is set
+    let ticket = metadata::extract_ticket(config, commit.description(), &bookmarks).map(|ticket| {
+        let bookmark = bookmarks.first().map(String::as_str);
+        let url = build_url(UrlKind::Ticket, bookmark, Some(&ticket));
+        (sanitize(&ticket).into_owned(), url)
+    });
+
+    // Forge links for the change id (`urls.change`, else `urls.commit`) and
+    // each bookmark (`urls.bookmark`)
+    let change_url =
+        build_url(UrlKind::Change, None, None).or_else(|| build_url(UrlKind::Commit, None, None));
+    let bookmark_urls = bookmarks
+        .iter()
+        .map(|name| build_url(UrlKind::Bookmark, Some(name), None))
+        .collect();
+
+    // Not-ready-to-push marker at the start of the description
+    let wip_prefix_len = metadata::wip_prefix_len(config, &description);
+
+    // Text standing in for a missing description, per `description-fallback`
+    let mut description_fallback = None;
+    if description.is_empty() {
+        for source in metadata::description_fallback(config) {
+            let text = match source {
+                DescriptionSource::Description => continue,
+                DescriptionSource::ChangeId => break,
+                DescriptionSource::ParentDescription => commit
+                    .parent_ids()
+                    .first()
+                    .and_then(|id| repo.store().get_commit(id).ok())
+                    .and_then(|parent| {
+                        Some(parent.description().lines().next()?.trim().to_string())
+                    }),
+                DescriptionSource::NearestBookmark => revsets
+                    .commit_ids(&format!("heads(::{at}- & bookmarks())"))
+                    .and_then(|ids| {
+                        let (name, _) = view.local_bookmarks_for_commit(ids.first()?).next()?;
+                        Some(name.as_str().to_string())
+                    }),
+            };
+            if let Some(text) = text.filter(|text| !text.is_empty()) {
+                description_fallback = Some((source, sanitize(&text).into_owned()));
+                break;
+            }
+        }
+    }
+
+    // Merge on @ or @-
+    let (merge_parent_count, merge_is_parent) = match commit.parent_ids() {
+        [] => (None, false),
+        [parent_id] => {
+            let parent_count = repo
+                .store()
+                .get_commit(parent_id)
+                .map_or(0, |parent| parent.parent_ids().len());
+            ((parent_count > 1).then_some(parent_count), parent_count > 1)
+        }
+        parent_ids => (Some(parent_ids.len()), false),
+    };
+
+    // Status indicators
+    let has_conflict = commit.has_conflict();
+    let conflict_count = if has_conflict {
+        commit.tree().conflicts().count()
+    } else {
+        0
+    };
+    // Divergence takes the same change id index as disambiguation
+    let divergent_count = if cli.no_disambiguate {
+        None
+    } else {
+        repo.resolve_change_id(commit.change_id())
+            .ok()
+            .flatten()
+            .map(|commits| commits.len())
+            .filter(|&count| count > 1)
+    };
+
+    // Changes at all, from the tree ids alone (cheaper than the file count)
+    let dirty = cli.dirty && commit.is_empty(repo.as_ref()).is_ok_and(|empty| !empty);
+
+    // Author identity check against jj's `user.email`, only for the working
+    // copy: other commits are routinely someone else's
+    let user_email = repo.settings().user_email();
+    let author_mismatch = cli.revision.is_none()
+        && !is_parent
+        && !user_email.is_empty()
+        && !commit.author().email.eq_ignore_ascii_case(user_email);
+
+    // Commit age
+    let committed_millis = commit.committer().timestamp.timestamp.0;
+    let now_millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as i64);
+    let age_secs = (now_millis - committed_millis) / 1000;
+
+    // Work journal (optional, appends to the state directory)
+    if history::is_enabled(config) && live {
+        let repo_root = dunce::canonicalize(repo_root).unwrap_or_else(|_| repo_root.to_path_buf());
+        history::record(
+            config,
+            &history::Entry {
+                repo: &repo_root,
+                change_id: &encode_reverse_hex(commit.change_id().as_bytes()),
+                commit_id: &commit.id().hex(),
+                op_id: &repo.op_id().hex(),
+                file_count,
+                untracked_count,
+                conflict_count,
+            },
+        );
+    }
+
+    // Bookmark names can hold control characters too; links used the raw names
+    let bookmarks = bookmarks
+        .iter()
+        .map(|name| sanitize(name).into_owned())
+        .collect();
+
+    Some(PromptInfo {
+        change_id,
+        prefix_len,
+        plugins: plugin_outputs,
+        bookmarks,
+        bookmark_urls,
+        change_url,
+        description,
+        wip_prefix_len,
+        description_fallback,
+        topic,
+        ticket,
+        merge_parent_count,
+        merge_is_parent,
+        has_conflict,
+        conflict_count,
+        divergent_count,
+        dirty,
+        file_count,
+        untracked_count,
+        stack_depth,
+        behind_trunk,
+        private_count,
+        stack_conflict_count,
+        stale_bookmark_count,
+        counters,
+        author_mismatch,
+        signature,
+        age_secs,
+        degraded,
+        parent,
+        is_parent,
+        templated: None,
+        slow_ms: None,
+    })
+}
+
+/// Zero-width sequence titling the terminal `repo:change_id description`
+fn title_sequence(repo_root: &Path, info: &PromptInfo, style: &Style) -> String {
+    let repo = repo_root.file_name().unwrap_or_default().to_string_lossy();
+    let mut title = format!("{repo}:{}", info.change_id);
+    if info.description != "(no description set)" {
+        title.push(' ');
+        title.push_str(&info.description);
+    }
+    style.escape.invisible(&set_title(&title))
+}
+
+/// Dimmed "last known" prompt from the session hints, marked as stale
+fn render_fallback(repo_root: &Path, cli: &Cli, style: &Style) -> Option<String> {
+    // Structured output has no values to report without the repo
+    if matches!(
+        style.output,
+        Output::OhMyPosh | Output::Nu | Output::Nvim | Output::Waybar | Output::I3statusRs
+    ) {
+        return None;
+    }
+    let change_id = hints::last_known_change_id(repo_root)?;
+    let change_id: String = change_id.chars().take(cli.id_length).collect();
+    let prompt = Prompt {
+        symbol: Some(Span::new(style.symbol.clone(), Role::Dim)),
+        segments: vec![
+            Segment::new(SegmentKind::ChangeId, change_id, Role::Dim),
+            Segment::new(SegmentKind::Status, &style.glyphs.last_known, Role::Dim),
+        ],
+    };
+    Some(prompt.encode(style))
+}
+
+fn render_prompt(info: &PromptInfo, cli: &Cli, style: &Style) -> String {
+    if style.output == Output::Nvim {
+        let (chunks, links) = match &info.templated {
+            // jj's colors have no highlight groups to go by
+            Some(templated) => {
+                let text = encode_templated(templated, style);
+                (vec![serde_json::json!([text, null])], Default::default())
+            }
+            None => fit_prompt(info, cli, style).nvim_chunks(style),
+        };
+        return nvim::encode(info, chunks, links);
+    }
+    let prompt = match &info.templated {
+        Some(templated) => encode_templated(templated, style),
+        None => encode_segments(info, cli, style),
+    };
+    match style.output {
+        Output::OhMyPosh => oh_my_posh::encode(info, &prompt),
+        Output::P10k => p10k::encode(info, &prompt),
+        Output::Nu => nu::encode(info, &prompt),
+        Output::Waybar => waybar::encode(info, &prompt),
+        Output::I3statusRs => i3status::encode(info, &prompt),
+        Output::Polybar if info.templated.is_some() => prompt.replace('%', "%%"),
+        Output::Emacs if info.templated.is_some() => {
+            segment::elisp_string(&prompt.replace('%', "%%"))
+        }
+        _ => prompt,
+    }
+}
+
+/// The built-in segments, shortened to `--max-width`
+fn encode_segments(info: &PromptInfo, cli: &Cli, style: &Style) -> String {
+    fit_prompt(info, cli, style).encode(style)
+}
+
+fn fit_prompt(info: &PromptInfo, cli: &Cli, style: &Style) -> Prompt {
+    let mut prompt = build_prompt(info, cli, style);
+    if style.side == Side::Right {
+        prompt.symbol = None;
+        prompt.trim();
+    }
+    // Shortening keeps the left end (change id first) in view on either side
+    if let Some(max) = cli.max_width {
+        // Powerline blocks are padded, joined by a glyph and end in one
+        let (separator, max) = match style.output {
+            Output::Powerline if style.colored => ("   ", max.saturating_sub(3)),
+            _ => (style.separator.as_str(), max),
+        };
+        fit_width(&mut prompt, info, style, separator, max);
+    }
+    prompt
+}
+
+/// A `--template` prompt as jj rendered it, keeping only its color codes (and
+/// only if colored, as ANSI), marked zero-width for the shell
+fn encode_templated(templated: &str, style: &Style) -> String {
+    let Ok(sgr) = regex::Regex::new(r"\x1b\[[0-9;:]*m") else {
+        return String::new();
+    };
+    let templated = templated.trim_end();
+    let mut encoded = String::new();
+    let mut end = 0;
+    for code in sgr.find_iter(templated) {
+        let text = sanitize(&templated[end..code.start()]);
+        encoded.push_str(&style.escape.text(&text));
+        if style.colored && !matches!(style.output, Output::Nvim | Output::Emacs | Output::Polybar)
+        {
+            encoded.push_str(&style.escape.invisible(code.as_str()));
+        }
+        end = code.end();
+    }
+    encoded.push_str(&style.escape.text(&sanitize(&templated[end..])));
+    encoded
+}
+
+fn build_prompt(info: &PromptInfo, cli: &Cli, style: &Style) -> Prompt {
+    let mut segments = Vec::new();
+
+    // Change ID with jj's native coloring
+    let prefix_len = Some(info.prefix_len)
+        .filter(|&len| info.change_id.is_char_boundary(len))
+        .unwrap_or(0);
+    let (prefix, suffix) = info.change_id.split_at(prefix_len);
+    let mut spans = vec![
+        Span::new(prefix, Role::ChangeIdPrefix),
+        Span::new(suffix, Role::ChangeIdRest),
+    ];
+    for span in &mut spans {
+        span.link = info.change_url.clone();
+    }
+    if info.is_parent {
+        spans.insert(0, Span::new(&style.glyphs.parent, Role::Dim));
+    }
+    segments.push(Segment {
+        kind: SegmentKind::ChangeId,
+        spans,
+    });
+
+    // Plugin output, as printed
+    for plugin in &info.plugins {
+        let text = bidi_isolate(&plugin.text, style.bidi);
+        segments.push(Segment::new(SegmentKind::Plugin, text, Role::Plain));
+    }
+
+    // Bookmarks (with jj's native coloring)
+    segments.extend(bookmarks_segment(info, style, cli.bookmarks_max));
+
+    // Topic chip (black on cyan)
+    if let Some(topic) = &info.topic {
+        let topic = bidi_isolate(topic, style.bidi);
+        let chip = match (style.colored, style.output) {
+            (false, _) => format!("[{}]", topic),
+            // Already on its own background
+            (true, Output::Powerline) => topic.to_string(),
+            (true, _) => format!(" {} ", topic),
+        };
+        segments.push(Segment::new(SegmentKind::Topic, chip, Role::Topic));
+    }
+
+    // Ticket id (blue, hyperlinked to the tracker when configured)
+    if let Some((ticket, url)) = &info.ticket {
+        let mut span = Span::new(bidi_isolate(ticket, style.bidi), Role::Ticket);
+        span.link = url.clone();
+        segments.push(Segment {
+            kind: SegmentKind::Ticket,
+            spans: vec![span],
+        });
+    }
+
+    // Merge glyph (dimmed with a trailing `-` when the merge is @-)
+    if let Some(parent_count) = info.merge_parent_count {
+        let mut merge = style.glyphs.merge.clone();
+        if cli.merge_parent_count {
+            merge.push_str(&parent_count.to_string());
+        }
+        let role = if info.merge_is_parent {
+            merge.push('-');
+            Role::Dim
+        } else {
+            Role::Plain
+        };
+        segments.push(Segment::new(SegmentKind::Merge, merge, role));
+    }
+
+    // Counts below their `[thresholds]` entry are left out
+    let thresholds = &style.thresholds;
+    let shown = |count: Option<usize>, threshold: usize| count.filter(|&n| n >= threshold);
+
+    // Stack depth above trunk (dimmed)
+    if let Some(depth) = shown(info.stack_depth, thresholds.stack_depth) {
+        let text = format!("{}{}", style.glyphs.stack_depth, depth);
+        segments.push(Segment::new(SegmentKind::StackDepth, text, Role::Dim));
+    }
+
+    // Behind trunk, rebase suggested (yellow)
+    if let Some(count) = shown(info.behind_trunk, thresholds.behind_trunk) {
+        let text = format!("{}{}", style.glyphs.behind_trunk, count);
+        segments.push(Segment::new(SegmentKind::BehindTrunk, text, Role::Warning));
+    }
+
+    // Private commits in the stack (red, never push these)
+    if let Some(count) = shown(info.private_count, thresholds.private) {
+        let text = format!("{}{}", style.glyphs.private, count);
+        segments.push(Segment::new(SegmentKind::Private, text, Role::Error));
+    }
+
+    // Status indicators (conflict and divergent), told apart by glyph as
+    // well as color
+    let mut spans = Vec::new();
+    if info.has_conflict {
+        let mut conflict = style.glyphs.conflict.clone();
+        if info.conflict_count > 0 {
+            conflict.push_str(&info.conflict_count.to_string());
+        }
+        spans.push(Span::new(conflict, Role::Error));
+    }
+    if let Some(count) = info.divergent_count {
+        let divergent = format!("{}{count}", style.glyphs.divergent);
+        spans.push(Span::new(divergent, Role::Warning));
+    }
+    if !spans.is_empty() {
+        segments.push(Segment {
+            kind: SegmentKind::Status,
+            spans,
+        });
+    }
+
+    // Signature status (green check, red cross, yellow for unverifiable)
+    if let Some(status) = info.signature {
+        let (glyph, role) = match status {
+            SigStatus::Good => (&style.glyphs.sig_good, Role::Success),
+            SigStatus::Bad => (&style.glyphs.sig_bad, Role::Error),
+            SigStatus::Unknown => (&style.glyphs.sig_unknown, Role::Warning),
+        };
+        segments.push(Segment::new(SegmentKind::Signature, glyph, role));
+    }
+
+    // Author mismatch (yellow warning)
+    if info.author_mismatch && !cli.no_author_check {
+        let glyph = &style.glyphs.author_mismatch;
+        segments.push(Segment::new(SegmentKind::Author, glyph, Role::Warning));
+    }
+
+    // Conflicts elsewhere in the stack (dimmed warning)
+    if let Some(count) = shown(info.stack_conflict_count, thresholds.stack_conflicts) {
+        let text = format!("{}{}", style.glyphs.stack_conflict, count);
+        segments.push(Segment::new(SegmentKind::StackConflicts, text, Role::Dim));
+    }
+
+    // Counters from the config (dimmed)
+    for counter in &info.counters {
+        let text = format!("{}{}", counter.icon, counter.count);
+        segments.push(Segment::new(SegmentKind::Counter, text, Role::Dim));
+    }
+
+    // Stale bookmark cleanup hint (dimmed)
+    if let Some(count) = shown(info.stale_bookmark_count, thresholds.stale_bookmarks) {
+        let text = format!("{}{}", style.glyphs.stale_bookmarks, count);
+        segments.push(Segment::new(SegmentKind::StaleBookmarks, text, Role::Dim));
+    }
+
+    // Dirty marker (dimmed)
+    if info.dirty {
+        let glyph = &style.glyphs.dirty;
+        segments.push(Segment::new(SegmentKind::Dirty, glyph, Role::Dim));
+    }
+
+    // File count (dimmed, escalating to warning and error colors)
+    if let Some(count) = shown(info.file_count, thresholds.file_count) {
+        let text = format!("{}{}", style.glyphs.file_count, count);
+        let role = thresholds.file_count_role(count);
+        segments.push(Segment::new(SegmentKind::FileCount, text, role));
+    }
+
+    // Untracked file count (dimmed)
+    if let Some(count) = shown(info.untracked_count, thresholds.untracked) {
+        let text = format!("{}{}", style.glyphs.untracked, count);
+        segments.push(Segment::new(SegmentKind::Untracked, text, Role::Dim));
+    }
+
+    // Commit age (dimmed, only past the threshold)
+    let age_threshold = cli
+        .age_threshold
+        .map_or(thresholds.age, |hours| hours as i64 * 3600);
+    if cli.age && info.age_secs >= age_threshold {
+        let age = text::format_age(info.age_secs);
+        segments.push(Segment::new(SegmentKind::Age, age, Role::Dim));
+    }
+
+    // Description (dimmed, skip if empty or default)
+    let description = match cli.description_length {
+        Some(max) => truncate_width(&info.description, max, &style.glyphs.ellipsis),
+        None => Cow::Borrowed(info.description.as_str()),
+    };
+    if !description.is_empty() && info.description != "(no description set)" {
+        // A WIP prefix is shown as a warning, the rest dimmed
+        let prefix_len = info
+            .wip_prefix_len
+            .filter(|&len| description.is_char_boundary(len))
+            .unwrap_or(0);
+        let (prefix, rest) = description.split_at(prefix_len);
+        let mut spans = Vec::new();
+        if !prefix.is_empty() {
+            spans.push(Span::new(prefix, Role::Warning));
+        }
+        spans.push(Span::new(bidi_isolate(rest, style.bidi), Role::Dim));
+        segments.push(Segment {
+            kind: SegmentKind::Description,
+            spans,
+        });
+    } else if let Some((source, text)) = &info.description_fallback {
+        let text = match cli.description_length {
+            Some(max) => truncate_width(text, max, &style.glyphs.ellipsis),
+            None => Cow::Borrowed(text.as_str()),
+        };
+        let role = match source {
+            DescriptionSource::NearestBookmark => Role::Bookmark,
+            _ => Role::Dim,
+        };
+        segments.push(Segment::new(
+            SegmentKind::Description,
+            bidi_isolate(&text, style.bidi),
+            role,
+        ));
+    }
+
+    // @-'s bookmarks and description, after an empty @
+    if let Some(parent) = &info.parent {
+        let mut spans = vec![Span::new(&style.glyphs.parent, Role::Dim)];
+        for (i, name) in parent.bookmarks.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::new(" ", Role::Plain));
+            }
+            spans.push(Span::new(bidi_isolate(name, style.bidi), Role::Bookmark));
+        }
+        if !parent.description.is_empty() {
+            if !parent.bookmarks.is_empty() {
+                spans.push(Span::new(" ", Role::Plain));
+            }
+            let description = match cli.description_length {
+                Some(max) => truncate_width(&parent.description, max, &style.glyphs.ellipsis),
+                None => Cow::Borrowed(parent.description.as_str()),
+            };
+            spans.push(Span::new(bidi_isolate(&description, style.bidi), Role::Dim));
+        }
+        segments.push(Segment {
+            kind: SegmentKind::Parent,
+            spans,
+        });
+    }
+
+    // Slow-prompt marker (dimmed), last so it doesn't shift the rest
+    if let Some(ms) = info.slow_ms {
+        let text = if cli.show_slow_ms {
+            format!("{}{}ms", style.glyphs.slow, ms)
+        } else {
+            style.glyphs.slow.clone()
+        };
+        segments.push(Segment::new(SegmentKind::Status, text, Role::Dim));
+    }
+
+    Prompt {
+        symbol: Some(Span::new(style.symbol.clone(), Role::Symbol)),
+        segments,
+    }
+}
+
+/// Bookmark names, at most `max` of them with the rest collapsed into `+N`
+fn bookmarks_segment(info: &PromptInfo, style: &Style, max: Option<usize>) -> Option<Segment> {
+    if info.bookmarks.is_empty() {
+        return None;
+    }
+    let shown = max.unwrap_or(usize::MAX);
+    let hidden = info.bookmarks.len().saturating_sub(shown);
+
+    let mut spans = Vec::new();
+    if !style.glyphs.bookmark.is_empty() {
+        spans.push(Span::new(&style.glyphs.bookmark, Role::Plain));
+    }
+    // One span per name, each linked to its forge URL when configured
+    for (i, name) in info.bookmarks.iter().take(shown).enumerate() {
+        if i > 0 {
+            spans.push(Span::new(" ", Role::Plain));
+        }
+        let mut span = Span::new(bidi_isolate(name, style.bidi), Role::Bookmark);
+        span.link = info.bookmark_urls.get(i).cloned().flatten();
+        spans.push(span);
+    }
+    // Collapsed remainder (dimmed)
+    if hidden > 0 {
+        if shown > 0 {
+            spans.push(Span::new(" ", Role::Plain));
+        }
+        spans.push(Span::new(format!("+{}", hidden), Role::Dim));
+    }
+    Some(Segment {
+        kind: SegmentKind::Bookmarks,
+        spans,
+    })
+}
+
+/// Shorten `prompt` to `max` columns. In order: truncate, then drop, the
+/// description (or @-'s, shown after an empty @); drop the file and untracked counts; collapse extra
+/// bookmarks; then drop segments from the right, keeping the change id
+fn fit_width(prompt: &mut Prompt, info: &PromptInfo, style: &Style, separator: &str, max: usize) {
+    let fits = |prompt: &Prompt| prompt.width(separator) <= max;
+    if fits(prompt) {
+        return;
+    }
+
+    if let Some(i) = prompt
+        .position(SegmentKind::Description)
+        .or_else(|| prompt.position(SegmentKind::Parent))
+    {
+        let others = prompt.width(separator) - prompt.segments[i].width();
+        let available = max.saturating_sub(others);
+        // Keep at least one character besides the ellipsis
+        if available > style.glyphs.ellipsis.width() {
+            prompt.segments[i].truncate(available, &style.glyphs.ellipsis);
+            return;
+        }
+        prompt.segments.remove(i);
+    }
+
+    for kind in [SegmentKind::FileCount, SegmentKind::Untracked] {
+        if fits(prompt) {
+            return;
+        }
+        prompt.remove(kind);
+    }
+
+    if !fits(prompt)
+        && let Some(i) = prompt.position(SegmentKind::Bookmarks)
+        && let Some(segment) = bookmarks_segment(info, style, Some(1))
+    {
+        prompt.segments[i] = segment;
+    }
+
+    while !fits(prompt) && prompt.segments.len() > 1 {
+        prompt.segments.pop();
+    }
+    if !fits(prompt) {
+        prompt.symbol = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// What bash shows for `PS1`, or None without a bash that has `@P`
+    fn bash_prompt(ps1: &str) -> Option<(String, String)> {
+        let output = Command::new("bash")
+            .args(["--norc", "-c", r#"PS1=$1; printf '%s' "${PS1@P}""#, "bash", ps1])
+            .output()
+            .ok()?;
+        output.status.success().then(|| {
+            (
+                String::from_utf8_lossy(&output.stdout).into_owned(),
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            )
+        })
+    }
+
+    #[test]
+    fn bash_prompt_keeps_description_literal() {
+        let description = r"feat: $(echo INJECTED >&2) `echo INJECTED >&2` ${HOME} \w \$ x";
+        let info = PromptInfo {
+            change_id: "pqxy".to_string(),
+            prefix_len: 1,
+            bookmarks: vec!["$(echo INJECTED >&2)".to_string()],
+            description: description.to_string(),
+            ..PromptInfo::default()
+        };
+        let cli = Cli::parse_from(["jj-prompt", "--escape", "bash", "--color", "never"]);
+        let style = Style::resolve(&cli, &Config::load(None));
+        let ps1 = render_prompt(&info, &cli, &style);
+
+        let Some((shown, stderr)) = bash_prompt(&ps1) else {
+            return;
+        };
+        assert!(shown.contains(description), "{shown:?}");
+        assert!(shown.contains("$(echo INJECTED >&2) "), "{shown:?}");
+        assert!(!stderr.contains("INJECTED"), "{stderr:?}");
+    }
+
+    /// Render `info` with `args`, kept within each width from 0 up
+    fn fit_each_width(info: &PromptInfo, args: &[&str]) {
+        let full = {
+            let cli = Cli::parse_from([&["jj-prompt", "--color", "never"], args].concat());
+            let style = Style::resolve(&cli, &Config::load(None));
+            render_prompt(info, &cli, &style).width()
+        };
+        for max in 0..=full + 1 {
+            let max_width = max.to_string();
+            let cli = Cli::parse_from(
+                [&["jj-prompt", "--color", "never", "--max-width", &max_width], args].concat(),
+            );
+            let style = Style::resolve(&cli, &Config::load(None));
+            let prompt = render_prompt(info, &cli, &style);
+            // The change id alone is never cut
+            let floor = info.change_id.width();
+            assert!(prompt.width() <= max.max(floor), "{max}: {prompt:?}");
+            assert!(prompt.contains(&info.change_id), "{max}: {prompt:?}");
+        }
+    }
+
+    #[test]
+    fn fit_width_handles_wide_text() {
+        let info = PromptInfo {
+            change_id: "pqxy".to_string(),
+            prefix_len: 1,
+            bookmarks: vec!["機能".to_string(), "👨\u{200d}👩\u{200d}👧".to_string()],
+            description: "wip: 日本語の説明 👨\u{200d}👩\u{200d}👧 and more".to_string(),
+            wip_prefix_len: Some(4),
+            file_count: Some(12),
+            untracked_count: Some(3),
+            ..PromptInfo::default()
+        };
+        fit_each_width(&info, &[]);
+        fit_each_width(&info, &["--untracked", "--ascii"]);
+        fit_each_width(&info, &["--separator", " · ", "--side", "right"]);
+    }
+
+    #[test]
+    fn fit_width_handles_rtl_text() {
+        let info = PromptInfo {
+            change_id: "pqxy".to_string(),
+            prefix_len: 1,
+            bookmarks: vec!["תיקון".to_string()],
+            description: "תיקון באג בתצוגה".to_string(),
+            parent: Some(ParentInfo {
+                bookmarks: vec!["main".to_string()],
+                description: "إصلاح الخطأ".to_string(),
+            }),
+            ..PromptInfo::default()
+        };
+        fit_each_width(&info, &[]);
+        fit_each_width(&info, &["--bidi", "always"]);
+    }
+
+    #[test]
+    fn nvim_groups_keep_the_wip_prefix_a_warning() {
+        let info = PromptInfo {
+            change_id: "pqxy".to_string(),
+            prefix_len: 1,
+            description: "wip: parser".to_string(),
+            wip_prefix_len: Some(4),
+            has_conflict: true,
+            divergent_count: Some(2),
+            ..PromptInfo::default()
+        };
+        let cli = Cli::parse_from(["jj-prompt", "--output", "nvim", "--color", "always"]);
+        let style = Style::resolve(&cli, &Config::load(None));
+        let record: serde_json::Value =
+            serde_json::from_str(&render_prompt(&info, &cli, &style)).unwrap();
+
+        let group_of = |text: &str| {
+            let chunks = record["chunks"].as_array().unwrap();
+            let chunk = chunks
+                .iter()
+                .find(|chunk| chunk[0].as_str().unwrap().starts_with(text))
+                .unwrap();
+            chunk[1].as_str().unwrap().to_string()
+        };
+        let link_of = |text: &str| record["links"][group_of(text)].clone();
+        assert_eq!(group_of("wip:"), "JjPromptDescriptionWarning");
+        assert_eq!(link_of("wip:"), "DiagnosticWarn");
+        assert_eq!(link_of(" parser"), "Comment");
+        assert_eq!(link_of(&style.glyphs.conflict), "DiagnosticError");
+        assert_eq!(link_of(&style.glyphs.divergent), "DiagnosticWarn");
+    }
+}
@@ -0,0 +1,164 @@
+//! `jj-prompt man`: roff man pages from the command-line definition
+//!
+//! The options and subcommands come from clap via clap_mangen, so they never
+//! drift from `--help`. The page for `jj-prompt` itself adds what `--help`
+//! can't hold: the segments, the config file, exit statuses and the
+//! environment. With `--dir`, each subcommand gets its own page next to it,
+//! for packagers.
+
+use clap::Command;
+use clap_mangen::Man;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Sections after the subcommands in `jj-prompt(1)`
+const EXTRA_SECTIONS: &str = r#".SH SEGMENTS
+Segments appear in this order, each only when it has something to show:
+.TP
+\fBchange id\fR
+The shortest unique prefix in bold magenta, the rest gray.
+.TP
+\fBplugins\fR
+The first line printed by each \fB[plugins]\fR command.
+.TP
+\fBbookmarks\fR
+Local bookmarks on the commit; \fB+N\fR for those hidden by \fB\-\-bookmarks\-max\fR.
+.TP
+\fBtopic\fR, \fBticket\fR
+From the description (\fBtopic\-pattern\fR, \fBticket\-pattern\fR) or a bookmark name.
+.TP
+\fB⑂\fR
+The commit is a merge; \fB⑂\-\fR when its parent is.
+.TP
+\fB⇡N\fR, \fB⇣N\fR
+Mutable commits above trunk (\fB\-\-stack\-depth\fR); trunk commits missing from @ (\fB\-\-behind\-trunk\fR).
+.TP
+\fB⊘N\fR
+Commits above trunk matching \fBprivate\-commits\fR.
+.TP
+\fB>N\fR, \fB\e\&N\fR
+Conflicted paths; visible commits sharing a divergent change id.
+.TP
+\fB✓\fR, \fB✗\fR, \fB?\fR, \fB✉\fR
+Signature status (\fB\-\-signature\fR); author email differing from \fBuser.email\fR.
+.TP
+\fB⚠N\fR
+Other conflicted commits in the stack (\fB\-\-stack\-conflicts\fR).
+.TP
+\fBcounters\fR
+The icon and match count of each \fB[counters]\fR revset.
+.TP
+\fB✂N\fR
+Bookmarks merged into trunk and safe to delete (\fB\-\-stale\-bookmarks\fR).
+.TP
+\fB*\fR, \fB~N\fR, \fB?N\fR
+Changes from the tree (\fB\-\-dirty\fR); changed files; untracked files (\fB\-\-untracked\fR).
+.TP
+\fBage\fR, \fBdescription\fR
+Time since the commit was rewritten (\fB\-\-age\fR); the first line of the description.
+.TP
+\fB⏱\fR
+The prompt took longer than \fB\-\-warn\-slow\-ms\fR to compute.
+.SH CONFIGURATION
+Settings are read from \fI$JJ_PROMPT_CONFIG\fR, else
+\fI$XDG_CONFIG_HOME/jj\-prompt/config.toml\fR (\fI~/.config\fR without it), then
+overridden per repo by \fI.jj/repo/jj\-prompt.toml\fR. Command\-line flags take
+precedence over both. Most flags have a key of the same name, e.g.
+\fBtimeout\-ms = 150\fR. Tables:
+.TP
+\fB[colors]\fR, \fB[glyphs]\fR
+Style and glyph of each segment, e.g. \fBdescription = "blue"\fR.
+.TP
+\fB[thresholds]\fR
+Smallest count (or age) shown for each segment.
+.TP
+\fB[urls]\fR, \fB[forge]\fR
+Hyperlink templates for the change, commit, bookmarks and tickets.
+.TP
+\fB[counters]\fR
+\fBname = { revset = "...", icon = "..." }\fR segments counting revset matches.
+.TP
+\fB[plugins]\fR
+\fBname = { command = "...", timeout\-ms = 200 }\fR segments from external commands.
+.TP
+\fB[history]\fR
+The JSONL log of computed prompt states.
+.PP
+jj's own settings (\fBuser.email\fR, \fBrevset\-aliases\fR, \fB[colors]\fR) are
+read from jj's config as jj reads them.
+.SH EXIT STATUS
+.TP
+\fB0\fR
+The prompt was printed.
+.TP
+\fB1\fR
+Not inside a jj repo, e.g. to fall back to a git prompt.
+.TP
+\fB2\fR
+The repo failed to load (or \fB\-\-write\-to\fR failed).
+.TP
+\fB3\fR
+A prompt was printed with parts left out, at \fB\-\-timeout\-ms\fR or from the session hints.
+.TP
+\fB4\fR
+Invalid arguments.
+.TP
+\fB5\fR
+Nothing was printed: the prompt is the one printed last time (\fB\-\-if\-changed\fR).
+.SH ENVIRONMENT
+.TP
+\fBJJ_PROMPT_CONFIG\fR
+The config file to read.
+.TP
+\fBJJ_PROMPT_STATE_DIR\fR
+Where caches and history are kept (default: \fI$XDG_CACHE_HOME/jj\-prompt\fR).
+.TP
+\fBJJ_PROMPT_JJ_BIN\fR
+The jj executable to run, as \fB\-\-jj\-bin\fR.
+.TP
+\fBJJ_PROMPT_LOG\fR
+A file to append a trace log to, as \fB\-\-log\-file\fR.
+.TP
+\fBJJ_PROMPT_BACKGROUND\fR, \fBCOLORFGBG\fR
+The terminal background, \fBdark\fR or \fBlight\fR.
+.TP
+\fBNO_COLOR\fR, \fBCLICOLOR_FORCE\fR
+Turn colors off or on with \fB\-\-color auto\fR.
+.TP
+\fBFORCE_HYPERLINK\fR
+\fB1\fR to always emit hyperlinks, \fB0\fR never.
+.TP
+\fBJJ_CONFIG\fR, \fBJJ_USER\fR, \fBJJ_EMAIL\fR
+As for jj.
+"#;
+
+/// Write `jj-prompt(1)` to stdout, or it and a page per subcommand to `dir`
+pub fn generate(cmd: Command, dir: Option<&Path>) -> io::Result<()> {
+    let mut cmd = cmd.disable_help_subcommand(true);
+    // Names subcommands `jj-prompt-<name>` for their pages
+    cmd.build();
+    let Some(dir) = dir else {
+        return render(&cmd, &mut io::stdout().lock());
+    };
+    fs::create_dir_all(dir)?;
+    let mut file = File::create(dir.join(Man::new(cmd.clone()).get_filename()))?;
+    render(&cmd, &mut file)?;
+    for subcommand in cmd.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        Man::new(subcommand.clone()).generate_to(dir)?;
+    }
+    Ok(())
+}
+
+/// `jj-prompt(1)`: clap's sections with ours before the version
+fn render(cmd: &Command, w: &mut dyn Write) -> io::Result<()> {
+    let man = Man::new(cmd.clone());
+    man.render_title(w)?;
+    man.render_name_section(w)?;
+    man.render_synopsis_section(w)?;
+    man.render_description_section(w)?;
+    man.render_options_section(w)?;
+    man.render_subcommands_section(w)?;
+    w.write_all(EXTRA_SECTIONS.as_bytes())?;
+    man.render_version_section(w)
+}
@@ -0,0 +1,122 @@
+//! Metadata extracted from commit descriptions
+//!
+//! The topic pattern is a regex whose first capture group is the topic. The
+//! optional ticket pattern also matches bookmark names, using its first
+//! capture group if it has one:
+//!
+//! ```toml
+//! topic-pattern = '\[topic:\s*([^\]]+)\]'
+//! ticket-pattern = '[A-Z]+-\d+'
+//! ```
+//!
+//! A commit without a description can borrow text from elsewhere, trying
+//! each source in turn:
+//!
+//! ```toml
+//! description-fallback = ["description", "parent-description", "nearest-bookmark"]
+//! ```
+
+use crate::config::Config;
+use regex::Regex;
+use serde::Deserialize;
+
+/// Matches `[topic: name]` anywhere, or a `Topic: name` trailer line
+const DEFAULT_TOPIC_PATTERN: &str = r"(?mi)\[topic:\s*([^\]]+?)\s*\]|^topic:\s*(\S.*?)\s*$";
+
+/// Description prefixes marking work that isn't ready to push
+const DEFAULT_WIP_PREFIXES: &[&str] = &["wip:", "fixup!", "squash!"];
+
+/// Where the description text comes from, tried in `description-fallback`
+/// order until one has something to show
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DescriptionSource {
+    /// The commit's own description
+    Description,
+    /// The first line of @-'s description
+    ParentDescription,
+    /// The closest bookmark among the commit's ancestors
+    NearestBookmark,
+    /// Nothing; the change id alone identifies the commit
+    ChangeId,
+}
+
+/// The configured fallback chain, by default the description alone
+pub fn description_fallback(config: &Config) -> Vec<DescriptionSource> {
+    config
+        .get("description-fallback")
+        .unwrap_or_else(|| vec![DescriptionSource::Description])
+}
+
+/// A topic and the description's first line with any inline marker removed
+pub struct Topic {
+    pub name: String,
+    pub first_line: Option<String>,
+}
+
+/// Find the topic in `description` using the configured or default pattern
+pub fn extract_topic(config: &Config, description: &str) -> Option<Topic> {
+    let pattern = config
+        .get::<String>("topic-pattern")
+        .unwrap_or_else(|| DEFAULT_TOPIC_PATTERN.to_string());
+    let regex = Regex::new(&pattern).ok()?;
+    let captures = regex.captures(description)?;
+    let name = captures
+        .iter()
+        .skip(1)
+        .flatten()
+        .next()?
+        .as_str()
+        .trim()
+        .to_string();
+    if name.is_empty() {
+        return None;
+    }
+
+    // Strip an inline marker so the topic isn't shown twice
+    let marker = captures.get(0)?;
+    let first_line_len = description.lines().next().unwrap_or("").len();
+    let first_line = (marker.end() <= first_line_len).then(|| {
+        let line = &description[..first_line_len];
+        format!("{}{}", &line[..marker.start()], &line[marker.end()..])
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+    Some(Topic { name, first_line })
+}
+
+/// Length of the WIP prefix (`wip-prefixes`, matched case-insensitively)
+/// that `first_line` starts with, if any
+pub fn wip_prefix_len(config: &Config, first_line: &str) -> Option<usize> {
+    let prefixes = config
+        .get::<Vec<String>>("wip-prefixes")
+        .unwrap_or_else(|| {
+            DEFAULT_WIP_PREFIXES
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect()
+        });
+    prefixes
+        .iter()
+        .filter(|prefix| !prefix.is_empty())
+        .find(|prefix| {
+            first_line
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        })
+        .map(|prefix| prefix.len())
+}
+
+/// Ticket id matching `ticket-pattern` in the description, else in the first
+/// bookmark name that has one
+pub fn extract_ticket(config: &Config, description: &str, bookmarks: &[String]) -> Option<String> {
+    let regex = Regex::new(&config.get::<String>("ticket-pattern")?).ok()?;
+    std::iter::once(description)
+        .chain(bookmarks.iter().map(String::as_str))
+        .find_map(|text| {
+            let captures = regex.captures(text)?;
+            let ticket = captures.get(1).or_else(|| captures.get(0))?;
+            Some(ticket.as_str().to_string()).filter(|ticket| !ticket.is_empty())
+        })
+}
@@ -0,0 +1,24 @@
+//! Structured output for nushell
+//!
+//! `--output nu` prints one JSON record: the colored prompt as `prompt` next
+//! to the raw values, for `from json` in a nushell prompt closure.
+
+use crate::values::Values;
+use crate::PromptInfo;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Record<'a> {
+    prompt: &'a str,
+    #[serde(flatten)]
+    values: Values<'a>,
+}
+
+/// `info` and the rendered `prompt` as a JSON record
+pub fn encode(info: &PromptInfo, prompt: &str) -> String {
+    serde_json::to_string(&Record {
+        prompt,
+        values: Values::new(info),
+    })
+    .unwrap_or_default()
+}
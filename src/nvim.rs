@@ -0,0 +1,35 @@
+//! Structured output for a Neovim statusline
+//!
+//! `--output nvim` prints one JSON record for a lualine component (or any
+//! `statusline` function): the plain prompt as `text`, the same text as
+//! `[text, group]` chunks like Neovim's virtual text, the built-in group each
+//! `JjPrompt...` group links to by default as `links`, and the raw values.
+
+use crate::values::Values;
+use crate::PromptInfo;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[derive(Serialize)]
+struct Record<'a> {
+    text: String,
+    chunks: Vec<Value>,
+    links: Map<String, Value>,
+    #[serde(flatten)]
+    values: Values<'a>,
+}
+
+/// `info` and the prompt's `chunks` as a JSON record
+pub fn encode(info: &PromptInfo, chunks: Vec<Value>, links: Map<String, Value>) -> String {
+    let text = chunks
+        .iter()
+        .filter_map(|chunk| chunk[0].as_str())
+        .collect();
+    serde_json::to_string(&Record {
+        text,
+        chunks,
+        links,
+        values: Values::new(info),
+    })
+    .unwrap_or_default()
+}
@@ -0,0 +1,35 @@
+//! JSON for an oh-my-posh segment
+//!
+//! `--output oh-my-posh` prints the plain prompt as `text` next to the raw
+//! values as `properties`, named like oh-my-posh template variables
+//! (`.ChangeId`, `.FileCount`, ...), so nothing has to parse colored text.
+
+use crate::values::Values;
+use crate::PromptInfo;
+use serde_json::{json, Map, Value};
+
+/// `info` and the rendered `text` as a JSON object
+pub fn encode(info: &PromptInfo, text: &str) -> String {
+    let properties: Map<String, Value> = match serde_json::to_value(Values::new(info)) {
+        Ok(Value::Object(values)) => values
+            .into_iter()
+            .map(|(key, value)| (pascal_case(&key), value))
+            .collect(),
+        _ => Map::new(),
+    };
+    json!({ "text": text, "properties": properties }).to_string()
+}
+
+/// `change_id` -> `ChangeId`
+fn pascal_case(key: &str) -> String {
+    key.split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
@@ -0,0 +1,13 @@
+//! Output for a Powerlevel10k custom segment
+//!
+//! `--output p10k` prints the plain prompt on the first line and a state on
+//! the second, which the segment passes to `p10k segment -s` so colors come
+//! from `POWERLEVEL9K_JJ_<STATE>_FOREGROUND` like any built-in segment.
+
+use crate::values::State;
+use crate::PromptInfo;
+
+/// The rendered `text`, then the state line
+pub fn encode(info: &PromptInfo, text: &str) -> String {
+    format!("{text}\n{}", State::of(info).name().to_uppercase())
+}
@@ -0,0 +1,64 @@
+//! Showing @- when @ is an empty, undescribed commit
+//!
+//! After `jj new`, @ has no changes and no description, and everything worth
+//! seeing (bookmarks, description) is on @-. With a fallback mode set, the
+//! prompt shows @- too, marked with the `parent` glyph:
+//!
+//! ```toml
+//! parent-fallback = "also"  # or "instead"
+//! ```
+
+use crate::text::sanitize;
+use clap::ValueEnum;
+use jj_lib::commit::Commit;
+use jj_lib::repo::Repo;
+use serde::{Deserialize, Serialize};
+
+/// What to show for an empty, undescribed @
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParentFallback {
+    /// @ as usual, followed by @-'s bookmarks and description
+    Also,
+    /// The prompt for @- in place of @
+    Instead,
+}
+
+/// Bookmarks and description of @-, shown after an empty @
+#[derive(Serialize)]
+pub struct ParentInfo {
+    pub bookmarks: Vec<String>,
+    /// First line of the description
+    pub description: String,
+}
+
+impl ParentInfo {
+    pub fn new(repo: &dyn Repo, parent: &Commit) -> Self {
+        ParentInfo {
+            bookmarks: repo
+                .view()
+                .local_bookmarks_for_commit(parent.id())
+                .map(|(name, _)| sanitize(name.as_str()).into_owned())
+                .collect(),
+            description: sanitize(parent.description().lines().next().unwrap_or("").trim())
+                .into_owned(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bookmarks.is_empty() && self.description.is_empty()
+    }
+}
+
+/// The first parent of `commit` if `commit` is empty with no description,
+/// unless that's the root commit
+pub fn blank_commit_parent(repo: &dyn Repo, commit: &Commit) -> Option<Commit> {
+    if !commit.description().trim().is_empty() || !commit.is_empty(repo).ok()? {
+        return None;
+    }
+    let parent_id = commit.parent_ids().first()?;
+    if parent_id == repo.store().root_commit_id() {
+        return None;
+    }
+    repo.store().get_commit(parent_id).ok()
+}
@@ -0,0 +1,107 @@
+//! Segments printed by external commands
+//!
+//! Each `[plugins]` entry is a shell command run from the repo root, whose
+//! first line of output is shown after the change id, e.g. the CI status of
+//! the change:
+//!
+//! ```toml
+//! [plugins.ci]
+//! command = "my-ci-status --short"
+//! timeout-ms = 300
+//! ```
+//!
+//! The command sees `JJ_PROMPT_REPO_ROOT`, `JJ_PROMPT_CHANGE_ID` and
+//! `JJ_PROMPT_COMMIT_ID` (both in full) in its environment. The plugins run
+//! side by side; one that fails, prints nothing, or outlives its timeout is
+//! left out.
+
+use crate::config::Config;
+use crate::process;
+use crate::text::sanitize;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default time a plugin gets before it's killed
+const DEFAULT_TIMEOUT_MS: u64 = 200;
+
+/// A `[plugins]` entry
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Plugin {
+    command: String,
+    timeout_ms: Option<u64>,
+}
+
+/// What a plugin printed, named as in the config
+#[derive(Serialize)]
+pub struct PluginOutput {
+    pub name: String,
+    pub text: String,
+}
+
+/// The commit the plugins are run for
+pub struct PluginEnv<'a> {
+    pub repo_root: &'a Path,
+    pub change_id: &'a str,
+    pub commit_id: &'a str,
+}
+
+/// The plugins in the order they're declared
+pub fn load(config: &Config) -> Vec<(String, Plugin)> {
+    config.table("plugins")
+}
+
+/// Run every plugin at once, each until its own timeout or `deadline`,
+/// whichever is first, and collect their output in order
+pub fn run_all(
+    plugins: Vec<(String, Plugin)>,
+    env: &PluginEnv,
+    deadline: Option<Instant>,
+) -> Vec<PluginOutput> {
+    thread::scope(|scope| {
+        let running: Vec<_> = plugins
+            .into_iter()
+            .map(|(name, plugin)| scope.spawn(move || run(name, plugin, env, deadline)))
+            .collect();
+        running
+            .into_iter()
+            .filter_map(|handle| handle.join().ok().flatten())
+            .collect()
+    })
+}
+
+fn run(
+    name: String,
+    plugin: Plugin,
+    env: &PluginEnv,
+    deadline: Option<Instant>,
+) -> Option<PluginOutput> {
+    let timeout = Duration::from_millis(plugin.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    let own_deadline = Instant::now() + timeout;
+    let deadline = deadline.map_or(own_deadline, |deadline| deadline.min(own_deadline));
+
+    let mut command = shell(&plugin.command);
+    command
+        .current_dir(env.repo_root)
+        .env("JJ_PROMPT_REPO_ROOT", env.repo_root)
+        .env("JJ_PROMPT_CHANGE_ID", env.change_id)
+        .env("JJ_PROMPT_COMMIT_ID", env.commit_id);
+    let stdout = process::output(command, Some(deadline), &format!("run plugin {name}"))?;
+    let text = sanitize(stdout.lines().next()?.trim()).into_owned();
+    (!text.is_empty()).then_some(PluginOutput { name, text })
+}
+
+/// `command` run by the platform's shell
+fn shell(command: &str) -> Command {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut shell = Command::new(shell);
+    shell.args([flag, command]);
+    shell
+}
@@ -0,0 +1,24 @@
+//! Click action for a polybar script module
+//!
+//! `--output polybar` colors the prompt with polybar's format tags and makes
+//! it clickable: a left click opens a terminal in the repo, with
+//! `open-command` from the config, else `$TERMINAL`, else
+//! `x-terminal-emulator`:
+//!
+//! ```toml
+//! open-command = "alacritty"
+//! ```
+
+use crate::config::Config;
+use std::path::Path;
+
+/// `prompt`, opening a terminal in `repo_root` when clicked
+pub fn clickable(prompt: &str, repo_root: &Path, config: &Config) -> String {
+    let open = config
+        .get::<String>("open-command")
+        .unwrap_or_else(|| "${TERMINAL:-x-terminal-emulator}".to_string());
+    let root = repo_root.to_string_lossy().replace('\'', r"'\''");
+    let command = format!("cd '{root}' && {open} &");
+    // A `:` would end the action early
+    format!("%{{A1:{}:}}{prompt}%{{A}}", command.replace(':', r"\:"))
+}
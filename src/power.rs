@@ -0,0 +1,89 @@
+//! Battery detection for low-power mode
+//!
+//! With the `battery` feature, the prompt drops its expensive segments (file
+//! count, untracked walk, revset-based hints) while running on battery:
+//!
+//! ```toml
+//! [low-power]
+//! enabled = true
+//! below-percent = 50  # only downgrade below this charge (default: 100)
+//! power-saver = true  # also downgrade in the Linux power-saver profile (default: true)
+//! ```
+
+use crate::config::Config;
+
+/// Whether low-power mode is enabled and the machine is currently on battery
+pub fn is_active(enabled: bool, config: &Config) -> bool {
+    if !enabled && !config.get::<bool>("low-power.enabled").unwrap_or(false) {
+        return false;
+    }
+    let below_percent = config.get("low-power.below-percent").unwrap_or(100);
+    let power_saver = config.get("low-power.power-saver").unwrap_or(true);
+    on_battery(below_percent) || (power_saver && in_power_saver())
+}
+
+#[cfg(all(feature = "battery", target_os = "linux"))]
+fn on_battery(below_percent: u8) -> bool {
+    use std::fs;
+    use std::path::Path;
+
+    let read = |path: &Path| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+    let Ok(supplies) = fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    let mut discharging = false;
+    for supply in supplies.flatten() {
+        let dir = supply.path();
+        match read(&dir.join("type")).as_deref() {
+            // Any connected charger means we're not on battery
+            Some("Mains" | "USB") if read(&dir.join("online")).as_deref() == Some("1") => {
+                return false;
+            }
+            Some("Battery") => {
+                let is_discharging = read(&dir.join("status")).as_deref() == Some("Discharging");
+                let capacity = read(&dir.join("capacity")).and_then(|c| c.parse::<u8>().ok());
+                if is_discharging && capacity.is_none_or(|c| c < below_percent) {
+                    discharging = true;
+                }
+            }
+            _ => {}
+        }
+    }
+    discharging
+}
+
+#[cfg(all(feature = "battery", target_os = "linux"))]
+fn in_power_saver() -> bool {
+    std::fs::read_to_string("/sys/firmware/acpi/platform_profile")
+        .is_ok_and(|profile| matches!(profile.trim(), "low-power" | "quiet"))
+}
+
+#[cfg(all(feature = "battery", target_os = "macos"))]
+fn on_battery(_below_percent: u8) -> bool {
+    // IOPSGetTimeRemainingEstimate returns kIOPSTimeRemainingUnlimited (-2.0)
+    // on external power. It doesn't expose the charge level, so on macOS any
+    // battery use counts as below the threshold.
+    #[link(name = "IOKit", kind = "framework")]
+    unsafe extern "C" {
+        fn IOPSGetTimeRemainingEstimate() -> f64;
+    }
+    const UNLIMITED: f64 = -2.0;
+    // SAFETY: takes no arguments and only reads power source state
+    let remaining = unsafe { IOPSGetTimeRemainingEstimate() };
+    remaining != UNLIMITED
+}
+
+#[cfg(all(feature = "battery", target_os = "macos"))]
+fn in_power_saver() -> bool {
+    false
+}
+
+#[cfg(not(all(feature = "battery", any(target_os = "linux", target_os = "macos"))))]
+fn on_battery(_below_percent: u8) -> bool {
+    false
+}
+
+#[cfg(not(all(feature = "battery", any(target_os = "linux", target_os = "macos"))))]
+fn in_power_saver() -> bool {
+    false
+}
@@ -0,0 +1,36 @@
+//! Shortest unique change id prefixes, cached per operation
+//!
+//! The prefix length only changes when the set of visible commits does, which
+//! takes an operation. The last length computed is kept in the repo's state
+//! directory with the operation and change id it's for, so prompts between
+//! operations skip the change id index.
+
+use std::fs;
+use std::path::Path;
+
+const FILE_NAME: &str = "prefix";
+
+/// The cached prefix length of `change_id` at operation `op_id`
+pub fn lookup(dir: &Path, op_id: &str, change_id: &str) -> Option<usize> {
+    let cached = fs::read_to_string(dir.join(FILE_NAME)).ok()?;
+    let mut fields = cached.split_whitespace();
+    if fields.next()? != op_id || fields.next()? != change_id {
+        return None;
+    }
+    fields.next()?.parse().ok()
+}
+
+/// Remember `len` for the next prompt. Failures are ignored; the prefix is
+/// simply computed again.
+pub fn store(dir: &Path, op_id: &str, change_id: &str, len: usize) {
+    if fs::create_dir_all(dir).is_err() {
+        return;
+    }
+    let path = dir.join(FILE_NAME);
+    let tmp = dir.join(format!("{FILE_NAME}.{}", std::process::id()));
+    if fs::write(&tmp, format!("{op_id} {change_id} {len}\n")).is_ok()
+        && fs::rename(&tmp, &path).is_err()
+    {
+        fs::remove_file(&tmp).ok();
+    }
+}
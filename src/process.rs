@@ -0,0 +1,44 @@
+//! Running helper processes within the time budget
+
+use crate::debug::{self, Traced};
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Run `command` and return its stdout, or `None` if it fails or is still
+/// running at `deadline` (it's killed then). `step` names the command for
+/// `--debug`.
+pub fn output(mut command: Command, deadline: Option<Instant>, step: &str) -> Option<String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .traced(step)?;
+    // Read on a thread so a full pipe can't stall the child while we wait
+    let mut pipe = child.stdout.take()?;
+    let reader = thread::spawn(move || {
+        let mut stdout = Vec::new();
+        pipe.read_to_end(&mut stdout).ok();
+        stdout
+    });
+    let status = loop {
+        if let Some(status) = child.try_wait().traced(step)? {
+            break status;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            child.kill().ok();
+            child.wait().ok();
+            debug::log(step, "stopped at the time budget");
+            return None;
+        }
+        thread::sleep(Duration::from_millis(2));
+    };
+    if !status.success() {
+        debug::log(step, status);
+        return None;
+    }
+    let stdout = reader.join().ok()?;
+    Some(String::from_utf8_lossy(&stdout).into_owned())
+}
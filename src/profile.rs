@@ -0,0 +1,58 @@
+//! `--profile`: how long each phase of the prompt took, on stderr
+//!
+//! ```text
+//! jj-prompt: load repo    8.1ms
+//! jj-prompt: prefix       0.4ms
+//! jj-prompt: file-count  41.7ms
+//! jj-prompt: total       52.3ms
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PHASES: Mutex<Vec<(&str, Duration)>> = Mutex::new(Vec::new());
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Note that `phase` took `elapsed`, also for the trace log
+pub fn record(phase: &'static str, elapsed: Duration) {
+    tracing::info!(phase, ?elapsed);
+    if ENABLED.load(Ordering::Relaxed)
+        && let Ok(mut phases) = PHASES.lock()
+    {
+        phases.push((phase, elapsed));
+    }
+}
+
+/// Run `f`, recording how long it took as `phase`
+pub fn time<T>(phase: &'static str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    record(phase, started.elapsed());
+    result
+}
+
+/// Print the recorded phases in order, then `total`
+pub fn report(total: Duration) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let phases = PHASES
+        .lock()
+        .map(|phases| phases.clone())
+        .unwrap_or_default();
+    let width = phases
+        .iter()
+        .map(|(phase, _)| phase.len())
+        .chain(["total".len()])
+        .max()
+        .unwrap_or_default();
+    for (phase, elapsed) in phases.iter().chain([&("total", total)]) {
+        let millis = elapsed.as_secs_f64() * 1000.0;
+        eprintln!("jj-prompt: {phase:<width$} {millis:>6.1}ms");
+    }
+}
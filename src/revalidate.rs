@@ -0,0 +1,126 @@
+//! Stale-while-revalidate: print the last prompt, refresh it in the background
+//!
+//! The prompt rendered last time for the same repo and flags is printed right
+//! away, and a detached copy of this process renders the current one into the
+//! cache for the next prompt. Only the first render in a repo waits for the
+//! repo to load. The refresh gets the colors resolved here, as it has no
+//! terminal to detect them from.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Set in the environment of the background refresh
+const REFRESH_VAR: &str = "JJ_PROMPT_REFRESH";
+
+/// Markers older than this belong to a crashed or killed refresh
+const STALE_MARKER: Duration = Duration::from_secs(10);
+
+/// The cached prompt for `key`, refreshed in the background, or `compute`'s
+/// result if nothing is cached yet
+pub fn run(
+    dir: &Path,
+    cwd: &Path,
+    key: u64,
+    colored: bool,
+    compute: impl FnOnce() -> Option<String>,
+) -> Option<String> {
+    let cache = dir.join(format!("cached-{key:016x}"));
+    let marker = dir.join(format!("refreshing-{key:016x}"));
+
+    // In the background refresh: render and store for the next prompt
+    if is_refresh() {
+        let output = compute();
+        if let Some(output) = &output {
+            store(dir, &cache, output);
+        }
+        fs::remove_file(&marker).ok();
+        return output;
+    }
+
+    match fs::read_to_string(&cache) {
+        Ok(cached) => {
+            spawn_refresh(cwd, &marker, colored);
+            Some(cached)
+        }
+        Err(_) => {
+            let output = compute();
+            if let Some(output) = &output {
+                store(dir, &cache, output);
+            }
+            output
+        }
+    }
+}
+
+/// Whether this process is a background refresh, whose output nobody sees
+pub fn is_refresh() -> bool {
+    env::var_os(REFRESH_VAR).is_some()
+}
+
+/// The command-line arguments, without the `--color` a refresh is given, so
+/// it computes the same cache key as the prompt that started it
+pub fn args() -> Vec<OsString> {
+    let mut args: Vec<_> = env::args_os().skip(1).collect();
+    if is_refresh() {
+        args.truncate(args.len().saturating_sub(2));
+    }
+    args
+}
+
+/// Start a detached refresh, unless one is already running
+fn spawn_refresh(cwd: &Path, marker: &Path, colored: bool) {
+    if !claim(marker) {
+        return;
+    }
+
+    let Ok(exe) = env::current_exe() else {
+        fs::remove_file(marker).ok();
+        return;
+    };
+    let mut command = Command::new(exe);
+    command
+        .args(env::args_os().skip(1))
+        .args(["--color", if colored { "always" } else { "never" }])
+        .current_dir(cwd)
+        .env(REFRESH_VAR, "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Out of the shell's process group, so Ctrl-C at the prompt spares it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    if command.spawn().is_err() {
+        fs::remove_file(marker).ok();
+    }
+}
+
+/// Create the in-flight marker, replacing one left by a dead refresh
+fn claim(marker: &Path) -> bool {
+    let create = || OpenOptions::new().write(true).create_new(true).open(marker);
+    match create() {
+        Ok(_) => true,
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+            let started = fs::metadata(marker).and_then(|m| m.modified());
+            let stale =
+                started.is_ok_and(|started| started.elapsed().unwrap_or_default() > STALE_MARKER);
+            stale && fs::remove_file(marker).is_ok() && create().is_ok()
+        }
+        Err(_) => false,
+    }
+}
+
+/// Replace the cache atomically, so a prompt never reads half of it
+fn store(dir: &Path, cache: &Path, output: &str) {
+    if fs::create_dir_all(dir).is_err() {
+        return;
+    }
+    let tmp = cache.with_extension(std::process::id().to_string());
+    if fs::write(&tmp, output).is_ok() && fs::rename(&tmp, cache).is_err() {
+        fs::remove_file(&tmp).ok();
+    }
+}
@@ -0,0 +1,133 @@
+//! Revset evaluation with jj's built-in aliases (`trunk()`, `mutable()`, ...)
+//!
+//! jj-lib only knows the primitive revset functions; the aliases users rely on
+//! are defined by jj-cli, so the defaults are mirrored here.
+
+use crate::debug::Traced;
+use chrono::Local;
+use jj_lib::backend::CommitId;
+use jj_lib::git;
+use jj_lib::repo::{ReadonlyRepo, Repo};
+use jj_lib::repo_path::RepoPathUiConverter;
+use jj_lib::revset::{
+    self, Revset, RevsetAliasesMap, RevsetDiagnostics, RevsetExtensions, RevsetParseContext,
+    RevsetWorkspaceContext, SymbolResolver,
+};
+use jj_lib::workspace::Workspace;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Default revset aliases from jj-cli's `config/revsets.toml`
+const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    (
+        "trunk()",
+        r#"latest(
+            remote_bookmarks(exact:"main", exact:"origin") |
+            remote_bookmarks(exact:"master", exact:"origin") |
+            remote_bookmarks(exact:"trunk", exact:"origin") |
+            remote_bookmarks(exact:"main", exact:"upstream") |
+            remote_bookmarks(exact:"master", exact:"upstream") |
+            remote_bookmarks(exact:"trunk", exact:"upstream") |
+            root()
+        )"#,
+    ),
+    (
+        "builtin_immutable_heads()",
+        "present(trunk()) | tags() | untracked_remote_bookmarks()",
+    ),
+    ("immutable_heads()", "builtin_immutable_heads()"),
+    ("immutable()", "::(immutable_heads() | root())"),
+    ("mutable()", "~immutable()"),
+    ("visible()", "::visible_heads()"),
+    ("hidden()", "~visible()"),
+];
+
+/// Evaluates revset expressions against a loaded workspace
+pub struct RevsetEvaluator<'a> {
+    workspace: &'a Workspace,
+    repo: &'a ReadonlyRepo,
+    aliases: RevsetAliasesMap,
+    path_converter: RepoPathUiConverter,
+    extensions: Arc<RevsetExtensions>,
+}
+
+impl<'a> RevsetEvaluator<'a> {
+    pub fn new(workspace: &'a Workspace, repo: &'a ReadonlyRepo) -> Self {
+        let mut aliases = RevsetAliasesMap::new();
+        for (decl, defn) in BUILTIN_ALIASES {
+            aliases
+                .insert(decl, *defn)
+                .expect("built-in revset aliases should parse");
+        }
+        // User-defined aliases (e.g. a custom `trunk()`) override the defaults
+        let config = repo.settings().config();
+        for decl in config.table_keys("revset-aliases") {
+            if let Ok(defn) = config.get::<String>(["revset-aliases", decl]) {
+                aliases.insert(decl, defn).ok();
+            }
+        }
+        let root = workspace.workspace_root().to_path_buf();
+        RevsetEvaluator {
+            workspace,
+            repo,
+            aliases,
+            path_converter: RepoPathUiConverter::Fs {
+                cwd: root.clone(),
+                base: root,
+            },
+            extensions: Arc::new(RevsetExtensions::new()),
+        }
+    }
+
+    /// Number of commits matching `expression`
+    pub fn count(&self, expression: &str) -> Option<usize> {
+        let revset = self.evaluate(expression)?;
+        Some(revset.iter().count())
+    }
+
+    /// Commit ids matching `expression`, newest first
+    pub fn commit_ids(&self, expression: &str) -> Option<Vec<CommitId>> {
+        let revset = self.evaluate(expression)?;
+        revset.iter().collect::<Result<_, _>>().ok()
+    }
+
+    /// How revsets are parsed here, for other languages that embed them
+    pub fn parse_context(&self) -> RevsetParseContext<'_> {
+        RevsetParseContext {
+            aliases_map: &self.aliases,
+            local_variables: HashMap::new(),
+            user_email: self.repo.settings().user_email(),
+            date_pattern_context: Local::now().into(),
+            default_ignored_remote: git::get_git_backend(self.repo.store())
+                .is_ok()
+                .then_some(git::REMOTE_NAME_FOR_LOCAL_GIT_REPO),
+            use_glob_by_default: false,
+            extensions: &self.extensions,
+            workspace: Some(RevsetWorkspaceContext {
+                path_converter: &self.path_converter,
+                workspace_name: self.workspace.workspace_name(),
+            }),
+        }
+    }
+
+    #[cfg(feature = "templates")]
+    pub fn path_converter(&self) -> &RepoPathUiConverter {
+        &self.path_converter
+    }
+
+    #[cfg(feature = "templates")]
+    pub fn extensions(&self) -> &Arc<RevsetExtensions> {
+        &self.extensions
+    }
+
+    fn evaluate(&self, expression: &str) -> Option<Box<dyn Revset + 'a>> {
+        let mut diagnostics = RevsetDiagnostics::new();
+        let expression = revset::parse(&mut diagnostics, expression, &self.parse_context())
+            .traced("parse revset")?;
+        let symbol_resolver = SymbolResolver::new(self.repo, self.extensions.symbol_resolvers());
+        let resolved = expression
+            .resolve_user_expression(self.repo, &symbol_resolver)
+            .traced("resolve revset")?;
+        resolved.evaluate(self.repo).traced("evaluate revset")
+    }
+}
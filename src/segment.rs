@@ -0,0 +1,706 @@
+//! Prompt segments: styled text runs that are measured and encoded last
+//!
+//! Building the prompt as segments rather than a finished string lets it be
+//! shortened to a width budget before any escape codes are written.
+
+use crate::style::Style;
+use crate::text::{hyperlink_start, truncate_width, HYPERLINK_END};
+use crate::theme::{Color, RESET};
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+use unicode_width::UnicodeWidthStr;
+
+/// How colors are encoded
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    /// ANSI escape codes
+    #[default]
+    Ansi,
+    /// ANSI background-colored blocks joined by powerline glyphs
+    Powerline,
+    /// tmux `#[fg=...]` styles, for `#(jj-prompt --output tmux)` in a status line
+    Tmux,
+    /// zjstatus `#[fg=...]` styles, for a command widget in a zellij bar
+    Zellij,
+    /// JSON with the plain prompt and its values, for an oh-my-posh segment
+    OhMyPosh,
+    /// Plain prompt, then a state line, for a Powerlevel10k segment
+    P10k,
+    /// JSON record with the ANSI prompt and its values, for nushell
+    Nu,
+    /// JSON list of `[text, [styles...]]` spans for Elvish's `styled`
+    Elvish,
+    /// JSON record with `[text, highlight group]` chunks and the values, for a
+    /// Neovim statusline component
+    Nvim,
+    /// Emacs Lisp string with `face` properties, for the mode line
+    Emacs,
+    /// JSON with the plain prompt, a tooltip and a state class, for a Waybar
+    /// custom module
+    Waybar,
+    /// `%{F#...}` format tags, clickable to open the repo, for a polybar
+    /// script module
+    Polybar,
+    /// JSON with the plain prompt and a state, for an i3status-rs custom block
+    #[value(name = "i3status-rs")]
+    I3statusRs,
+}
+
+/// How to mark escape codes as zero-width when the prompt is used directly
+/// in a shell's PS1, so line editing measures the prompt correctly
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Escape {
+    /// Raw escape codes (starship and most other hosts)
+    #[default]
+    None,
+    /// `%{...%}`, with `%` in text doubled
+    Zsh,
+    /// `\[...\]`, with `\`, `$` and `` ` `` in text kept from being expanded
+    Bash,
+}
+
+impl Escape {
+    /// Mark `code` as not taking up any columns
+    pub fn invisible(self, code: &str) -> String {
+        // The shell expands what's inside the markers too, e.g. a URL's `%`
+        // or `$`, and the `\` ending an OSC sequence
+        let code = self.text(code);
+        match self {
+            Escape::None => code.into_owned(),
+            Escape::Zsh => format!("%{{{code}%}}"),
+            Escape::Bash => format!("\\[{code}\\]"),
+        }
+    }
+
+    /// Keep the shell from interpreting prompt sequences in `text`
+    pub fn text(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Escape::None => Cow::Borrowed(text),
+            Escape::Zsh => Cow::Owned(text.replace('%', "%%")),
+            // PS1 is decoded, then expanded like a double-quoted string.
+            // `\\` decodes to `\`, which then quotes the next character.
+            Escape::Bash => Cow::Owned(
+                text.replace('\\', "\\\\\\\\")
+                    .replace('$', "\\\\$")
+                    .replace('`', "\\\\`"),
+            ),
+        }
+    }
+}
+
+/// What a span's color means, mapped to escape codes at encoding time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Plain,
+    Symbol,
+    ChangeIdPrefix,
+    ChangeIdRest,
+    Bookmark,
+    Dim,
+    Warning,
+    Success,
+    Error,
+    Topic,
+    Ticket,
+}
+
+impl Role {
+    /// Whether the role paints a background, making padding visible
+    fn has_background(self) -> bool {
+        self == Role::Topic
+    }
+
+    fn tmux(self) -> &'static str {
+        match self {
+            Role::Plain => "",
+            Role::Symbol => "#[fg=green]",
+            Role::ChangeIdPrefix => "#[bold,fg=colour5]",
+            Role::ChangeIdRest => "#[fg=colour8]",
+            Role::Bookmark => "#[fg=colour5]",
+            Role::Dim => "#[dim]",
+            Role::Warning => "#[fg=yellow]",
+            Role::Success => "#[fg=green]",
+            Role::Error => "#[fg=red]",
+            Role::Topic => "#[fg=black,bg=cyan]",
+            Role::Ticket => "#[fg=blue]",
+        }
+    }
+
+    /// zjstatus styles, with 256-color numbers since it has no named colors
+    fn zellij(self) -> &'static str {
+        match self {
+            Role::Plain => "",
+            Role::Symbol | Role::Success => "#[fg=2]",
+            Role::ChangeIdPrefix => "#[fg=5,bold]",
+            Role::ChangeIdRest | Role::Dim => "#[fg=8]",
+            Role::Bookmark => "#[fg=5]",
+            Role::Warning => "#[fg=3]",
+            Role::Error => "#[fg=1]",
+            Role::Topic => "#[fg=0,bg=6]",
+            Role::Ticket => "#[fg=4]",
+        }
+    }
+
+    /// polybar foreground and background, xterm's defaults for the colors
+    /// tmux and zellij get by number
+    fn polybar(self) -> (Option<&'static str>, Option<&'static str>) {
+        match self {
+            Role::Plain => (None, None),
+            Role::Symbol | Role::Success => (Some("#00cd00"), None),
+            Role::ChangeIdPrefix | Role::Bookmark => (Some("#cd00cd"), None),
+            Role::ChangeIdRest | Role::Dim => (Some("#7f7f7f"), None),
+            Role::Warning => (Some("#cdcd00"), None),
+            Role::Error => (Some("#cd0000"), None),
+            Role::Topic => (Some("#000000"), Some("#00cdcd")),
+            Role::Ticket => (Some("#5c5cff"), None),
+        }
+    }
+
+    /// Elvish `styled` transformers
+    fn elvish(self) -> &'static [&'static str] {
+        match self {
+            Role::Plain => &[],
+            Role::Symbol | Role::Success => &["green"],
+            Role::ChangeIdPrefix => &["bold", "magenta"],
+            Role::ChangeIdRest => &["bright-black"],
+            Role::Bookmark => &["magenta"],
+            Role::Dim => &["dim"],
+            Role::Warning => &["yellow"],
+            Role::Error => &["red"],
+            Role::Topic => &["black", "bg-cyan"],
+            Role::Ticket => &["blue"],
+        }
+    }
+
+    /// Built-in Neovim highlight group the role's groups link to by default
+    fn nvim_link(self) -> Option<&'static str> {
+        match self {
+            Role::Plain => None,
+            Role::Symbol | Role::Success => Some("DiagnosticOk"),
+            Role::ChangeIdPrefix => Some("Special"),
+            Role::ChangeIdRest | Role::Dim => Some("Comment"),
+            Role::Bookmark => Some("Identifier"),
+            Role::Warning => Some("DiagnosticWarn"),
+            Role::Error => Some("DiagnosticError"),
+            Role::Topic => Some("Search"),
+            Role::Ticket => Some("Underlined"),
+        }
+    }
+
+    /// Emacs face for the role, from the built-in ones themes style
+    fn emacs_face(self) -> Option<&'static str> {
+        match self {
+            Role::Plain => None,
+            Role::Symbol | Role::Success => Some("success"),
+            Role::ChangeIdPrefix => Some("(:inherit font-lock-keyword-face :weight bold)"),
+            Role::ChangeIdRest | Role::Dim => Some("shadow"),
+            Role::Bookmark => Some("font-lock-constant-face"),
+            Role::Warning => Some("warning"),
+            Role::Error => Some("error"),
+            Role::Topic => Some("highlight"),
+            Role::Ticket => Some("link"),
+        }
+    }
+}
+
+/// A run of text in one color, optionally hyperlinked
+pub struct Span {
+    pub text: String,
+    pub role: Role,
+    pub link: Option<String>,
+}
+
+impl Span {
+    pub fn new(text: impl Into<String>, role: Role) -> Self {
+        Span {
+            text: text.into(),
+            role,
+            link: None,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.text.width()
+    }
+}
+
+/// Which part of the prompt a segment shows, used to pick what to shorten
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    ChangeId,
+    /// A `[plugins]` command's output
+    Plugin,
+    Bookmarks,
+    Topic,
+    Ticket,
+    Merge,
+    StackDepth,
+    BehindTrunk,
+    Private,
+    Status,
+    Signature,
+    Author,
+    StackConflicts,
+    /// A `[counters]` entry
+    Counter,
+    StaleBookmarks,
+    Dirty,
+    FileCount,
+    Untracked,
+    Age,
+    Description,
+    Parent,
+}
+
+impl SegmentKind {
+    /// Key under `[colors]` for a span with `role` in this segment
+    pub fn style_key(self, role: Role) -> &'static str {
+        match self {
+            SegmentKind::ChangeId if role == Role::ChangeIdPrefix => "change-id-prefix",
+            // The marker of @- shown in place of @
+            SegmentKind::ChangeId if role == Role::Dim => "parent",
+            SegmentKind::ChangeId => "change-id-rest",
+            SegmentKind::Plugin => "plugins",
+            SegmentKind::Bookmarks => "bookmarks",
+            SegmentKind::Topic => "topic",
+            SegmentKind::Ticket => "ticket",
+            SegmentKind::Merge => "merge",
+            SegmentKind::StackDepth => "stack-depth",
+            SegmentKind::BehindTrunk => "behind-trunk",
+            SegmentKind::Private => "private",
+            SegmentKind::Status => "status",
+            SegmentKind::Signature => "signature",
+            SegmentKind::Author => "author",
+            SegmentKind::StackConflicts => "stack-conflicts",
+            SegmentKind::Counter => "counters",
+            SegmentKind::StaleBookmarks => "stale-bookmarks",
+            SegmentKind::Dirty => "dirty",
+            SegmentKind::FileCount => "file-count",
+            SegmentKind::Untracked => "untracked",
+            SegmentKind::Age => "age",
+            SegmentKind::Description => "description",
+            SegmentKind::Parent => "parent",
+        }
+    }
+
+    /// Role of the segment's usual text, whose spans get the bare
+    /// `JjPrompt<Key>` group in Neovim. Spans in other roles get the role
+    /// appended, e.g. `JjPromptDescriptionWarning` for a WIP prefix. None
+    /// where `style_key` already tells the roles apart.
+    fn main_role(self) -> Option<Role> {
+        match self {
+            SegmentKind::ChangeId => None,
+            SegmentKind::Plugin => Some(Role::Plain),
+            SegmentKind::Bookmarks => Some(Role::Bookmark),
+            SegmentKind::Topic => Some(Role::Topic),
+            SegmentKind::Ticket => Some(Role::Ticket),
+            SegmentKind::BehindTrunk | SegmentKind::Author => Some(Role::Warning),
+            SegmentKind::Private | SegmentKind::Status => Some(Role::Error),
+            SegmentKind::Signature => Some(Role::Success),
+            SegmentKind::Merge
+            | SegmentKind::StackDepth
+            | SegmentKind::StackConflicts
+            | SegmentKind::Counter
+            | SegmentKind::StaleBookmarks
+            | SegmentKind::Dirty
+            | SegmentKind::FileCount
+            | SegmentKind::Untracked
+            | SegmentKind::Age
+            | SegmentKind::Description
+            | SegmentKind::Parent => Some(Role::Dim),
+        }
+    }
+
+    /// 256-color background and foreground of the segment's powerline block
+    fn powerline_colors(self) -> (u8, u8) {
+        const WHITE: u8 = 15;
+        const BLACK: u8 = 0;
+        match self {
+            SegmentKind::ChangeId => (5, WHITE),
+            SegmentKind::Bookmarks => (53, WHITE),
+            SegmentKind::Topic => (6, BLACK),
+            SegmentKind::Ticket => (4, WHITE),
+            SegmentKind::Status | SegmentKind::Private => (1, WHITE),
+            SegmentKind::BehindTrunk | SegmentKind::Author | SegmentKind::Signature => (3, BLACK),
+            SegmentKind::Merge
+            | SegmentKind::StackDepth
+            | SegmentKind::StackConflicts
+            | SegmentKind::Counter
+            | SegmentKind::Plugin
+            | SegmentKind::StaleBookmarks
+            | SegmentKind::Dirty
+            | SegmentKind::FileCount
+            | SegmentKind::Untracked
+            | SegmentKind::Age => (238, WHITE),
+            SegmentKind::Description | SegmentKind::Parent => (236, 250),
+        }
+    }
+}
+
+/// One separator-delimited part of the prompt
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub spans: Vec<Span>,
+}
+
+impl Segment {
+    /// A segment with a single span
+    pub fn new(kind: SegmentKind, text: impl Into<String>, role: Role) -> Self {
+        Segment {
+            kind,
+            spans: vec![Span::new(text, role)],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.spans.iter().map(Span::width).sum()
+    }
+
+    /// Cut to at most `max` columns, ending in `ellipsis`
+    pub fn truncate(&mut self, max: usize, ellipsis: &str) {
+        if self.width() <= max {
+            return;
+        }
+        let mut budget = max.saturating_sub(ellipsis.width());
+        for (i, span) in self.spans.iter_mut().enumerate() {
+            let width = span.width();
+            if width > budget {
+                let kept = truncate_width(&span.text, budget, "");
+                span.text = format!("{}{ellipsis}", kept.trim_end());
+                self.spans.truncate(i + 1);
+                return;
+            }
+            budget -= width;
+        }
+    }
+}
+
+/// The symbol followed by separator-joined segments
+pub struct Prompt {
+    pub symbol: Option<Span>,
+    pub segments: Vec<Segment>,
+}
+
+impl Prompt {
+    /// Display width in terminal columns
+    pub fn width(&self, separator: &str) -> usize {
+        let symbol = self.symbol.as_ref().map_or(0, Span::width);
+        let segments: usize = self.segments.iter().map(Segment::width).sum();
+        let separators = self.segments.len().saturating_sub(1) * separator.width();
+        symbol + segments + separators
+    }
+
+    pub fn position(&self, kind: SegmentKind) -> Option<usize> {
+        self.segments
+            .iter()
+            .position(|segment| segment.kind == kind)
+    }
+
+    pub fn remove(&mut self, kind: SegmentKind) {
+        self.segments.retain(|segment| segment.kind != kind);
+    }
+
+    /// Strip whitespace from both ends of the prompt, except padding that
+    /// is part of a background color
+    pub fn trim(&mut self) {
+        let spans = self.symbol.iter_mut().chain(
+            self.segments
+                .iter_mut()
+                .flat_map(|segment| &mut segment.spans),
+        );
+        let mut spans: Vec<&mut Span> = spans.filter(|span| !span.text.is_empty()).collect();
+        if let Some(first) = spans.first_mut()
+            && !first.role.has_background()
+        {
+            first.text = first.text.trim_start().to_string();
+        }
+        if let Some(last) = spans.last_mut()
+            && !last.role.has_background()
+        {
+            last.text = last.text.trim_end().to_string();
+        }
+    }
+
+    /// Encode per `style` (output format, escaping, color)
+    pub fn encode(&self, style: &Style) -> String {
+        let (separator, colored, output, escape) =
+            (&style.separator, style.colored, style.output, style.escape);
+        if colored && output == Output::Powerline {
+            return self.encode_powerline(style);
+        }
+        if output == Output::Elvish {
+            return self.encode_elvish(style);
+        }
+        if output == Output::Emacs {
+            return self.encode_emacs(style);
+        }
+        let mut encoded = String::new();
+        // `key` picks the `[colors]` override; empty for separators
+        let mut push_span = |span: &Span, key: &str| {
+            let text = match output {
+                Output::Ansi
+                | Output::Powerline
+                | Output::OhMyPosh
+                | Output::P10k
+                | Output::Nu
+                | Output::Waybar
+                | Output::I3statusRs => escape.text(&span.text),
+                // `#` starts a format sequence in tmux status lines
+                Output::Tmux => Cow::Owned(span.text.replace('#', "##")),
+                // Keep text from opening a zjstatus style
+                Output::Zellij => Cow::Owned(span.text.replace("#[", "# [")),
+                // `%{` starts a polybar format tag
+                Output::Polybar => Cow::Owned(span.text.replace('%', "%%")),
+                Output::Elvish | Output::Nvim | Output::Emacs => unreachable!("encoded apart"),
+            };
+            if !colored {
+                encoded.push_str(&text);
+                return;
+            }
+            match output {
+                Output::Ansi
+                | Output::Powerline
+                | Output::OhMyPosh
+                | Output::P10k
+                | Output::Nu
+                | Output::Waybar
+                | Output::I3statusRs => {
+                    let text_style = style.overrides.apply(key, style.palette.get(span.role));
+                    let sgr = text_style.sgr(style.depth);
+                    if !sgr.is_empty() {
+                        encoded.push_str(&escape.invisible(&sgr));
+                    }
+                    match span.link.as_ref().filter(|_| style.hyperlinks) {
+                        Some(url) => {
+                            encoded.push_str(&escape.invisible(&hyperlink_start(url)));
+                            encoded.push_str(&text);
+                            encoded.push_str(&escape.invisible(HYPERLINK_END));
+                        }
+                        None => encoded.push_str(&text),
+                    }
+                    if !sgr.is_empty() {
+                        encoded.push_str(&escape.invisible(RESET));
+                    }
+                }
+                Output::Tmux | Output::Zellij | Output::Polybar if span.role == Role::Plain => {
+                    encoded.push_str(&text);
+                }
+                Output::Tmux => {
+                    encoded.push_str(span.role.tmux());
+                    encoded.push_str(&text);
+                    encoded.push_str("#[default]");
+                }
+                Output::Elvish | Output::Nvim | Output::Emacs => unreachable!("encoded apart"),
+                Output::Zellij => {
+                    encoded.push_str(span.role.zellij());
+                    encoded.push_str(&text);
+                    encoded.push_str("#[default]");
+                }
+                Output::Polybar => {
+                    let (fg, bg) = span.role.polybar();
+                    if let Some(bg) = bg {
+                        encoded.push_str(&format!("%{{B{bg}}}"));
+                    }
+                    if let Some(fg) = fg {
+                        encoded.push_str(&format!("%{{F{fg}}}"));
+                    }
+                    encoded.push_str(&text);
+                    if fg.is_some() {
+                        encoded.push_str("%{F-}");
+                    }
+                    if bg.is_some() {
+                        encoded.push_str("%{B-}");
+                    }
+                }
+            }
+        };
+
+        if let Some(symbol) = &self.symbol {
+            push_span(symbol, "symbol");
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                push_span(&Span::new(separator, Role::Plain), "");
+            }
+            for span in &segment.spans {
+                push_span(span, segment.kind.style_key(span.role));
+            }
+        }
+        encoded
+    }
+
+    /// Spans as `[text, [styles...]]`, so Elvish styles them itself rather
+    /// than showing escape codes literally
+    fn encode_elvish(&self, style: &Style) -> String {
+        let mut spans = Vec::new();
+        let mut push_span = |span: &Span| {
+            let styles = if style.colored {
+                span.role.elvish()
+            } else {
+                &[]
+            };
+            spans.push(json!([span.text, styles]));
+        };
+        if let Some(symbol) = &self.symbol {
+            push_span(symbol);
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                push_span(&Span::new(&style.separator, Role::Plain));
+            }
+            segment.spans.iter().for_each(&mut push_span);
+        }
+        Value::Array(spans).to_string()
+    }
+
+    /// A propertized string in Emacs's read syntax, `#("text" 0 4 (face
+    /// ...) ...)`, with `%` doubled for the mode line
+    fn encode_emacs(&self, style: &Style) -> String {
+        let mut text = String::new();
+        let mut properties = String::new();
+        let mut chars = 0;
+        let mut push_span = |span: &Span| {
+            let span_text = span.text.replace('%', "%%");
+            let start = chars;
+            chars += span_text.chars().count();
+            text.push_str(&span_text);
+            if let Some(face) = span.role.emacs_face().filter(|_| style.colored)
+                && start < chars
+            {
+                properties.push_str(&format!(" {start} {chars} (face {face})"));
+            }
+        };
+        if let Some(symbol) = &self.symbol {
+            push_span(symbol);
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                push_span(&Span::new(&style.separator, Role::Plain));
+            }
+            segment.spans.iter().for_each(&mut push_span);
+        }
+        if properties.is_empty() {
+            elisp_string(&text)
+        } else {
+            format!("#({}{properties})", elisp_string(&text))
+        }
+    }
+
+    /// Spans as `[text, group]` chunks, with a `JjPrompt...` highlight group
+    /// per `[colors]` key and role (null for plain text), and the built-in
+    /// group each of those links to by default
+    pub fn nvim_chunks(&self, style: &Style) -> (Vec<Value>, Map<String, Value>) {
+        let mut chunks = Vec::new();
+        let mut links = Map::new();
+        let mut push_span = |span: &Span, key: &str, main_role: Option<Role>| {
+            let link = span.role.nvim_link().filter(|_| style.colored);
+            let group = link.map(|link| {
+                let mut group = nvim_group(key);
+                if main_role.is_some_and(|role| role != span.role) {
+                    group.push_str(&format!("{:?}", span.role));
+                }
+                links.insert(group.clone(), json!(link));
+                group
+            });
+            chunks.push(json!([span.text, group]));
+        };
+        if let Some(symbol) = &self.symbol {
+            push_span(symbol, "symbol", None);
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                push_span(&Span::new(&style.separator, Role::Plain), "", None);
+            }
+            for span in &segment.spans {
+                let key = segment.kind.style_key(span.role);
+                push_span(span, key, segment.kind.main_role());
+            }
+        }
+        (chunks, links)
+    }
+
+    /// Blocks with background colors, each followed by a glyph drawn in the
+    /// block's color over the next block's background
+    fn encode_powerline(&self, style: &Style) -> String {
+        let (glyph, thin) = (&style.glyphs.powerline, &style.glyphs.powerline_thin);
+        let escape = style.escape;
+        let code = |code: String| escape.invisible(&code);
+        // Palette indices, reduced on 16-color terminals
+        let fg_code = |n: u8| {
+            code(format!(
+                "\x1b[{}m",
+                Color::Indexed(n).sgr(false, style.depth)
+            ))
+        };
+        let bg_code = |n: u8| {
+            code(format!(
+                "\x1b[{}m",
+                Color::Indexed(n).sgr(true, style.depth)
+            ))
+        };
+        let mut encoded = String::new();
+        let mut previous_bg = None;
+        for (i, segment) in self.segments.iter().enumerate() {
+            let (bg, fg) = segment.kind.powerline_colors();
+            match previous_bg {
+                // Same color on both sides: a thin divider in the text color
+                Some(previous_bg) if previous_bg == bg => {
+                    encoded.push_str(&fg_code(fg));
+                    encoded.push_str(&escape.text(thin));
+                }
+                Some(previous_bg) => {
+                    encoded.push_str(&fg_code(previous_bg));
+                    encoded.push_str(&bg_code(bg));
+                    encoded.push_str(&escape.text(glyph));
+                }
+                None => encoded.push_str(&bg_code(bg)),
+            }
+            encoded.push_str(&fg_code(fg));
+            encoded.push(' ');
+            if i == 0
+                && let Some(symbol) = &self.symbol
+            {
+                encoded.push_str(&escape.text(symbol.text.trim()));
+                encoded.push(' ');
+            }
+            for span in &segment.spans {
+                // Keep the change id prefix bold, drop other span colors
+                if span.role == Role::ChangeIdPrefix {
+                    encoded.push_str(&code("\x1b[1m".to_string()));
+                    encoded.push_str(&escape.text(&span.text));
+                    encoded.push_str(&code("\x1b[22m".to_string()));
+                } else {
+                    encoded.push_str(&escape.text(&span.text));
+                }
+            }
+            encoded.push(' ');
+            previous_bg = Some(bg);
+        }
+        if let Some(previous_bg) = previous_bg {
+            encoded.push_str(&code(RESET.to_string()));
+            encoded.push_str(&fg_code(previous_bg));
+            encoded.push_str(&escape.text(glyph));
+            encoded.push_str(&code(RESET.to_string()));
+        }
+        encoded
+    }
+}
+
+/// Neovim highlight group for a `[colors]` key, e.g. `JjPromptChangeIdPrefix`
+fn nvim_group(key: &str) -> String {
+    let mut group = String::from("JjPrompt");
+    for word in key.split('-') {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            group.extend(first.to_uppercase());
+            group.push_str(chars.as_str());
+        }
+    }
+    group
+}
+
+/// `text` as an Emacs Lisp string literal
+pub fn elisp_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,39 @@
+//! Per-repo cache and state directories
+//!
+//! Defaults to `$XDG_CACHE_HOME/jj-prompt` (or `~/.cache/jj-prompt`). Users
+//! with home directories on NFS can point this at local disk or tmpfs with
+//! `$JJ_PROMPT_STATE_DIR` or `state-dir` in the user or per-repo config.
+
+use crate::config::{expand_home, Config};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Base directory for all jj-prompt state
+pub fn base_dir(config: &Config) -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("JJ_PROMPT_STATE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = config.get::<String>("state-dir") {
+        return Some(expand_home(&dir));
+    }
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join("jj-prompt"))
+}
+
+/// State directory for the repo at `repo_root`, keyed by its canonical path
+pub fn repo_dir(config: &Config, repo_root: &Path) -> Option<PathBuf> {
+    let root = dunce::canonicalize(repo_root).unwrap_or_else(|_| repo_root.to_path_buf());
+    let key = fnv1a(root.as_os_str().as_encoded_bytes());
+    Some(base_dir(config)?.join("repos").join(format!("{key:016x}")))
+}
+
+/// FNV-1a: stable across Rust versions, unlike `DefaultHasher`
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    bytes
+        .iter()
+        .fold(OFFSET, |hash, &b| (hash ^ u64::from(b)).wrapping_mul(PRIME))
+}
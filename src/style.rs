@@ -0,0 +1,346 @@
+//! Presentation settings resolved from flags and config
+//!
+//! ```toml
+//! separator = " · "  # between segments (default: a single space)
+//! theme = "dracula"  # color theme (default: "jj-native")
+//! background = "light"  # terminal background (default: detect, else dark)
+//! icons = "nerd"     # glyph preset (default: "default")
+//! ascii = true       # plain ASCII glyphs only, for non-UTF-8 terminals
+//! hyperlinks = true  # force OSC 8 links on/off (default: detect the terminal)
+//! truecolor = true   # force 24-bit colors on/off (default: from $COLORTERM)
+//! color-depth = "16"  # or "256", "truecolor" (default: from $COLORTERM, $TERM)
+//!
+//! [glyphs]
+//! conflict = "!"     # any indicator glyph can be overridden
+//! divergent = "÷"
+//! ```
+
+use crate::config::Config;
+use crate::console;
+use crate::segment::{Escape, Output};
+use crate::text::BidiMode;
+use crate::theme::{ColorDepth, Overrides, Palette, Theme};
+use crate::thresholds::Thresholds;
+use crate::Cli;
+use clap::ValueEnum;
+use jj_lib::config::StackedConfig;
+use serde::Deserialize;
+
+/// Symbol shown before the change id
+const DEFAULT_SYMBOL: &str = " \u{eafc} ";
+
+/// Glyph presets, refined by `[glyphs]` and flags
+#[derive(Clone, Copy, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum IconSet {
+    /// Unicode symbols that render in most fonts
+    #[default]
+    Default,
+    /// Nerd Font glyphs, matching starship's look
+    Nerd,
+    /// Plain ASCII, for terminals without UTF-8
+    Ascii,
+}
+
+/// Which side of the command line the prompt is drawn on
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Side {
+    /// Main prompt, led by the symbol
+    #[default]
+    Left,
+    /// RPROMPT / starship `right_format`: no symbol or surrounding whitespace
+    Right,
+}
+
+/// When to emit colors
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Unless NO_COLOR is set; always with CLICOLOR_FORCE; else when stdout
+    /// or stderr is a terminal, or under starship
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// How segments are joined and decorated
+pub struct Style {
+    /// Shown before the change id
+    pub symbol: String,
+    /// Placed between segments
+    pub separator: String,
+    pub glyphs: Glyphs,
+    /// Bidi isolation for user text (never in ASCII mode)
+    pub bidi: BidiMode,
+    pub side: Side,
+    /// False with `--color never` (or auto without a terminal), or for hosts
+    /// that apply their own colors
+    pub colored: bool,
+    pub theme: Theme,
+    /// Colors of each role in ANSI output
+    pub palette: Palette,
+    /// Per-segment styles from `[colors]`, over the palette
+    pub overrides: Overrides,
+    /// Colors the terminal shows; others are sent as the nearest it has
+    pub depth: ColorDepth,
+    /// Wrap linked spans in OSC 8 hyperlinks
+    pub hyperlinks: bool,
+    /// Segments below these values are hidden
+    pub thresholds: Thresholds,
+    pub output: Output,
+    pub escape: Escape,
+}
+
+/// Indicator glyphs, each overridable under `[glyphs]`
+pub struct Glyphs {
+    /// Prefix for the bookmark list (default: none)
+    pub bookmark: String,
+    pub conflict: String,
+    pub divergent: String,
+    pub merge: String,
+    pub stack_depth: String,
+    pub behind_trunk: String,
+    pub private: String,
+    pub sig_good: String,
+    pub sig_bad: String,
+    pub sig_unknown: String,
+    pub author_mismatch: String,
+    pub stack_conflict: String,
+    pub stale_bookmarks: String,
+    pub dirty: String,
+    pub file_count: String,
+    pub untracked: String,
+    /// Ends a truncated description
+    pub ellipsis: String,
+    /// Marks the fallback prompt rendered from session hints
+    pub last_known: String,
+    /// Marks @- shown for an empty @
+    pub parent: String,
+    /// Marks a prompt that took longer than `--warn-slow-ms`
+    pub slow: String,
+    /// Between powerline blocks
+    pub powerline: String,
+    /// Between powerline blocks of the same color
+    pub powerline_thin: String,
+}
+
+impl Default for Glyphs {
+    fn default() -> Self {
+        Glyphs {
+            bookmark: String::new(),
+            conflict: ">".to_string(),
+            divergent: "\\".to_string(),
+            merge: "⑂".to_string(),
+            stack_depth: "⇡".to_string(),
+            behind_trunk: "⇣".to_string(),
+            private: "⊘".to_string(),
+            sig_good: "✓".to_string(),
+            sig_bad: "✗".to_string(),
+            sig_unknown: "?".to_string(),
+            author_mismatch: "✉".to_string(),
+            stack_conflict: "⚠".to_string(),
+            stale_bookmarks: "✂".to_string(),
+            dirty: "*".to_string(),
+            file_count: "~".to_string(),
+            untracked: "?".to_string(),
+            ellipsis: "…".to_string(),
+            last_known: "≈".to_string(),
+            parent: "↰".to_string(),
+            slow: "⏱".to_string(),
+            powerline: "\u{e0b0}".to_string(),
+            powerline_thin: "\u{e0b1}".to_string(),
+        }
+    }
+}
+
+impl Glyphs {
+    /// Glyphs for `set`, with the defaults for anything it doesn't change
+    fn preset(set: IconSet) -> Self {
+        match set {
+            IconSet::Default => Glyphs::default(),
+            IconSet::Nerd => Glyphs {
+                bookmark: "\u{f461} ".to_string(),
+                conflict: "\u{f421} ".to_string(),
+                divergent: "\u{f402} ".to_string(),
+                file_count: "\u{f440} ".to_string(),
+                ..Glyphs::default()
+            },
+            IconSet::Ascii => Glyphs {
+                merge: "Y".to_string(),
+                stack_depth: "^".to_string(),
+                behind_trunk: "v".to_string(),
+                private: "P".to_string(),
+                sig_good: "+".to_string(),
+                sig_bad: "!".to_string(),
+                author_mismatch: "@".to_string(),
+                stack_conflict: "#".to_string(),
+                stale_bookmarks: "x".to_string(),
+                ellipsis: "...".to_string(),
+                last_known: "*".to_string(),
+                parent: "@-".to_string(),
+                slow: "T".to_string(),
+                powerline: ">".to_string(),
+                powerline_thin: "|".to_string(),
+                ..Glyphs::default()
+            },
+        }
+    }
+
+    /// Every glyph with its config key
+    fn entries(&mut self) -> [(&'static str, &mut String); 22] {
+        [
+            ("glyphs.bookmark", &mut self.bookmark),
+            ("glyphs.conflict", &mut self.conflict),
+            ("glyphs.divergent", &mut self.divergent),
+            ("glyphs.merge", &mut self.merge),
+            ("glyphs.stack-depth", &mut self.stack_depth),
+            ("glyphs.behind-trunk", &mut self.behind_trunk),
+            ("glyphs.private", &mut self.private),
+            ("glyphs.sig-good", &mut self.sig_good),
+            ("glyphs.sig-bad", &mut self.sig_bad),
+            ("glyphs.sig-unknown", &mut self.sig_unknown),
+            ("glyphs.author-mismatch", &mut self.author_mismatch),
+            ("glyphs.stack-conflict", &mut self.stack_conflict),
+            ("glyphs.stale-bookmarks", &mut self.stale_bookmarks),
+            ("glyphs.dirty", &mut self.dirty),
+            ("glyphs.file-count", &mut self.file_count),
+            ("glyphs.untracked", &mut self.untracked),
+            ("glyphs.ellipsis", &mut self.ellipsis),
+            ("glyphs.last-known", &mut self.last_known),
+            ("glyphs.parent", &mut self.parent),
+            ("glyphs.slow", &mut self.slow),
+            ("glyphs.powerline", &mut self.powerline),
+            ("glyphs.powerline-thin", &mut self.powerline_thin),
+        ]
+    }
+
+    /// Replace glyphs set under `[glyphs]` in the config
+    fn apply_config(&mut self, config: &Config) {
+        for (key, glyph) in self.entries() {
+            if let Some(value) = config.get(key) {
+                *glyph = value;
+            }
+        }
+    }
+
+    /// Undo any override that isn't plain ASCII
+    fn restrict_to_ascii(&mut self) {
+        let mut ascii = Glyphs::preset(IconSet::Ascii);
+        for ((_, glyph), (_, fallback)) in self.entries().into_iter().zip(ascii.entries()) {
+            if !glyph.is_ascii() {
+                *glyph = std::mem::take(fallback);
+            }
+        }
+    }
+}
+
+impl Style {
+    /// Flags take precedence over the config. ASCII mode wins over both and
+    /// drops any non-ASCII symbol, separator, or glyph.
+    pub fn resolve(cli: &Cli, config: &Config) -> Self {
+        let ascii = cli.ascii || config.get("ascii").unwrap_or(false);
+        let keep = |value: &String| !ascii || value.is_ascii();
+
+        let separator = cli
+            .separator
+            .clone()
+            .or_else(|| config.get("separator"))
+            .filter(keep)
+            .unwrap_or_else(|| " ".to_string());
+
+        let icons = if ascii {
+            IconSet::Ascii
+        } else {
+            cli.icons
+                .or_else(|| config.get("icons"))
+                .unwrap_or_default()
+        };
+        let symbol = cli
+            .symbol
+            .clone()
+            .or_else(|| config.get("symbol"))
+            .filter(keep)
+            .unwrap_or_else(|| match icons {
+                IconSet::Default => DEFAULT_SYMBOL.to_string(),
+                IconSet::Nerd => " \u{f418} ".to_string(),
+                IconSet::Ascii => " jj ".to_string(),
+            });
+
+        let mut glyphs = Glyphs::preset(icons);
+        glyphs.apply_config(config);
+        if let Some(glyph) = &cli.conflict_glyph {
+            glyphs.conflict = glyph.clone();
+        }
+        if let Some(glyph) = &cli.divergent_glyph {
+            glyphs.divergent = glyph.clone();
+        }
+        if ascii {
+            glyphs.restrict_to_ascii();
+        }
+
+        let choice = if cli.no_color {
+            ColorChoice::Never
+        } else {
+            cli.color
+        };
+        let colored = match cli.output {
+            // oh-my-posh, p10k, Waybar and i3status-rs color the plain text
+            // themselves
+            Output::OhMyPosh | Output::P10k | Output::Waybar | Output::I3statusRs => false,
+            _ if choice == ColorChoice::Never => false,
+            // Always read by a program that renders the styles
+            Output::Tmux
+            | Output::Zellij
+            | Output::Polybar
+            | Output::Elvish
+            | Output::Nvim
+            | Output::Emacs => true,
+            Output::Ansi | Output::Powerline | Output::Nu => {
+                (choice == ColorChoice::Always || console::auto_color()) && console::enable_ansi()
+            }
+        };
+
+        let theme: Theme = cli
+            .theme
+            .or_else(|| config.get("theme"))
+            .unwrap_or_default();
+
+        let background = cli
+            .background
+            .or_else(|| config.get("background"))
+            .or_else(console::background_hint)
+            .unwrap_or_default();
+
+        Style {
+            symbol,
+            separator,
+            glyphs,
+            bidi: if ascii { BidiMode::Never } else { cli.bidi },
+            side: cli.side,
+            colored,
+            theme,
+            palette: theme.palette(background),
+            overrides: Overrides::load(config),
+            depth: config
+                .get("color-depth")
+                .unwrap_or_else(|| match config.get("truecolor") {
+                    Some(true) => ColorDepth::Truecolor,
+                    Some(false) => console::color_depth().min(ColorDepth::Indexed),
+                    None => console::color_depth(),
+                }),
+            hyperlinks: config
+                .get("hyperlinks")
+                .unwrap_or_else(console::supports_hyperlinks),
+            thresholds: Thresholds::load(config),
+            output: cli.output,
+            escape: cli.escape,
+        }
+    }
+
+    /// Match the user's `jj log` colors, unless they picked another theme
+    pub fn apply_jj_colors(&mut self, jj_config: &StackedConfig) {
+        if self.theme == Theme::JjNative {
+            self.palette.apply_jj_colors(jj_config);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! jj's commit template language, as in `jj log -T`
+//!
+//! Built with `--features templates`, which links jj-cli for its template
+//! engine. Templates see the user's `template-aliases` on top of jj's
+//! built-in ones, so the prompt can reuse the formatting from `jj log`:
+//!
+//! ```toml
+//! description-template = "format_short_description(self)"
+//! ```
+
+use crate::debug::{self, Traced};
+use crate::revsets::RevsetEvaluator;
+use jj_cli::cli_util::load_template_aliases;
+use jj_cli::commit_templater::{CommitTemplateLanguage, CommitTemplateLanguageExtension};
+use jj_cli::formatter::{ColorFormatter, PlainTextFormatter};
+use jj_cli::template_builder;
+use jj_cli::template_parser::{TemplateAliasesMap, TemplateDiagnostics};
+use jj_cli::ui::Ui;
+use jj_lib::commit::Commit;
+use jj_lib::conflicts::ConflictMarkerStyle;
+use jj_lib::id_prefix::IdPrefixContext;
+use jj_lib::repo::{ReadonlyRepo, Repo};
+use jj_lib::revset::{self, RevsetDiagnostics, UserRevsetExpression};
+use jj_lib::workspace::Workspace;
+use std::sync::Arc;
+
+/// Renders commit templates for one repo
+pub struct CommitTemplates<'a> {
+    workspace: &'a Workspace,
+    repo: &'a ReadonlyRepo,
+    revsets: &'a RevsetEvaluator<'a>,
+    aliases: TemplateAliasesMap,
+    id_prefix_context: IdPrefixContext,
+    immutable: Arc<UserRevsetExpression>,
+}
+
+impl<'a> CommitTemplates<'a> {
+    pub fn new(
+        workspace: &'a Workspace,
+        repo: &'a ReadonlyRepo,
+        revsets: &'a RevsetEvaluator<'a>,
+    ) -> Option<Self> {
+        // With jj's defaults (`builtin_*` templates, `revsets.log`, ...) under
+        // the user's config; see `config::load_jj_settings`
+        let config = repo.settings().config();
+        let aliases = load_template_aliases(&Ui::null(), config)
+            .map_err(|err| debug::log("load template aliases", format!("{:?}", err.error)))
+            .ok()?;
+
+        let parse_revset = |text: &str| {
+            let mut diagnostics = RevsetDiagnostics::new();
+            revset::parse(&mut diagnostics, text, &revsets.parse_context()).traced("parse revset")
+        };
+        // Shortest ids are unique within `revsets.short-prefixes`, as in jj
+        let short_prefixes = config
+            .get::<String>("revsets.short-prefixes")
+            .or_else(|_| config.get::<String>("revsets.log"))
+            .ok()
+            .filter(|text| !text.is_empty());
+        let mut id_prefix_context = IdPrefixContext::new(revsets.extensions().clone());
+        if let Some(expression) = short_prefixes.as_deref().and_then(parse_revset) {
+            id_prefix_context = id_prefix_context.disambiguate_within(expression);
+        }
+        let immutable = parse_revset("immutable()")?;
+
+        Some(CommitTemplates {
+            workspace,
+            repo,
+            revsets,
+            aliases,
+            id_prefix_context,
+            immutable,
+        })
+    }
+
+    /// `template` applied to `commit`, as plain text or with the ANSI colors
+    /// of jj's `[colors]`
+    pub fn render(&self, template: &str, commit: &Commit, colored: bool) -> Option<String> {
+        let extensions: &[Arc<dyn CommitTemplateLanguageExtension>] = &[];
+        let language = CommitTemplateLanguage::new(
+            self.repo as &dyn Repo,
+            self.revsets.path_converter(),
+            self.workspace.workspace_name(),
+            self.revsets.parse_context(),
+            &self.id_prefix_context,
+            self.immutable.clone(),
+            self.repo
+                .settings()
+                .get("ui.conflict-marker-style")
+                .unwrap_or(ConflictMarkerStyle::Diff),
+            extensions,
+        );
+        let mut diagnostics = TemplateDiagnostics::new();
+        let renderer =
+            template_builder::parse(&language, &mut diagnostics, template, &self.aliases)
+                .traced("parse template")?;
+        let mut output = Vec::new();
+        if colored {
+            let config = self.repo.settings().config();
+            let mut formatter =
+                ColorFormatter::for_config(&mut output, config, false).traced("read colors")?;
+            renderer.format(commit, &mut formatter)
+        } else {
+            renderer.format(commit, &mut PlainTextFormatter::new(&mut output))
+        }
+        .traced("render template")?;
+        Some(String::from_utf8_lossy(&output).into_owned())
+    }
+}
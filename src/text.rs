@@ -0,0 +1,237 @@
+//! Text handling for user-controlled strings and compact formatting
+
+use clap::ValueEnum;
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// FIRST STRONG ISOLATE: direction is taken from the isolated text itself
+const FSI: char = '\u{2068}';
+/// POP DIRECTIONAL ISOLATE: closes the isolate opened by FSI
+const PDI: char = '\u{2069}';
+
+/// When to wrap user text in Unicode bidi isolates
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum BidiMode {
+    /// Isolate only text containing right-to-left characters
+    #[default]
+    Auto,
+    /// Isolate all user text
+    Always,
+    /// Never emit bidi control characters
+    Never,
+}
+
+/// Wrap `text` in FSI/PDI so RTL runs can't reorder the surrounding segments
+pub fn bidi_isolate(text: &str, mode: BidiMode) -> Cow<'_, str> {
+    let isolate = match mode {
+        BidiMode::Always => !text.is_empty(),
+        BidiMode::Auto => text.chars().any(is_rtl),
+        BidiMode::Never => false,
+    };
+    if isolate {
+        Cow::Owned(format!("{FSI}{text}{PDI}"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Whether `c` belongs to a right-to-left script block
+fn is_rtl(c: char) -> bool {
+    matches!(
+        c,
+        // Hebrew, Arabic, Syriac, Thaana, NKo, Samaritan, Mandaic, Arabic Extended
+        '\u{0590}'..='\u{08FF}'
+            // Hebrew and Arabic presentation forms
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFC}'
+            // Historic RTL scripts (Phoenician, Kharoshthi, ...) and Adlam etc.
+            | '\u{10800}'..='\u{10FFF}'
+            | '\u{1E800}'..='\u{1EFFF}'
+            // Explicit RTL marks and overrides
+            | '\u{200F}'
+            | '\u{202B}'
+            | '\u{202E}'
+            | '\u{2067}'
+    )
+}
+
+/// Replace control characters with visible escapes (`\u{1b}`), so text from
+/// the repo can't recolor the prompt or send commands to the terminal
+pub fn sanitize(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(char::is_control) {
+        return Cow::Borrowed(text);
+    }
+    let mut sanitized = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_control() {
+            sanitized.extend(c.escape_default());
+        } else {
+            sanitized.push(c);
+        }
+    }
+    Cow::Owned(sanitized)
+}
+
+/// Shorten `text` to at most `max` terminal columns, ending in `ellipsis` if
+/// cut. Grapheme clusters (wide characters, emoji sequences) are never
+/// split, and a bidi isolate around the text is kept closed.
+pub fn truncate_width<'a>(text: &'a str, max: usize, ellipsis: &str) -> Cow<'a, str> {
+    if text.width() <= max {
+        return Cow::Borrowed(text);
+    }
+    if let Some(inner) = text
+        .strip_prefix(FSI)
+        .and_then(|text| text.strip_suffix(PDI))
+    {
+        return Cow::Owned(format!(
+            "{FSI}{}{PDI}",
+            truncate_width(inner, max, ellipsis)
+        ));
+    }
+    let budget = max.saturating_sub(ellipsis.width());
+    let mut width = 0;
+    let mut cut = 0;
+    for (index, grapheme) in text.grapheme_indices(true) {
+        width += grapheme.width();
+        if width > budget {
+            break;
+        }
+        cut = index + grapheme.len();
+    }
+    if budget == 0 && ellipsis.width() > max {
+        return Cow::Borrowed("");
+    }
+    Cow::Owned(format!("{}{ellipsis}", text[..cut].trim_end()))
+}
+
+/// `text` with `&`, `<` and `>` escaped for Pango markup
+pub fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Start an OSC 8 terminal hyperlink to `url`
+pub fn hyperlink_start(url: &str) -> String {
+    format!("\x1b]8;;{url}\x1b\\")
+}
+
+/// End an OSC 8 terminal hyperlink
+pub const HYPERLINK_END: &str = "\x1b]8;;\x1b\\";
+
+/// OSC 2 sequence setting the terminal window/tab title to `title`, with
+/// control characters dropped so it can't end the sequence early
+pub fn set_title(title: &str) -> String {
+    let title: String = title.chars().filter(|c| !c.is_control()).collect();
+    format!("\x1b]2;{title}\x07")
+}
+
+/// Compact relative age like "45m", "3h", "2d", "6w", "1y"
+pub fn format_age(seconds: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const WEEK: i64 = 7 * DAY;
+    const YEAR: i64 = 365 * DAY;
+
+    let seconds = seconds.max(0);
+    match seconds {
+        s if s < HOUR => format!("{}m", s / MINUTE),
+        s if s < DAY => format!("{}h", s / HOUR),
+        s if s < WEEK => format!("{}d", s / DAY),
+        s if s < YEAR => format!("{}w", s / WEEK),
+        s => format!("{}y", s / YEAR),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_keeps_short_text() {
+        assert_eq!(truncate_width("feat", 4, "…"), "feat");
+        assert_eq!(truncate_width("", 0, "…"), "");
+    }
+
+    #[test]
+    fn truncate_counts_wide_characters_as_two() {
+        assert_eq!(truncate_width("日本語の説明", 5, "…"), "日本…");
+        assert_eq!(truncate_width("日本語の説明", 6, "…"), "日本…");
+        assert_eq!(truncate_width("日本語の説明", 7, "…"), "日本語…");
+    }
+
+    #[test]
+    fn truncate_never_splits_emoji_sequences() {
+        // Family: man, ZWJ, woman, ZWJ, girl; a flag is two regional indicators
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let text = format!("{family}{family} 🇳🇱 done");
+        for max in 0..=text.width() {
+            let truncated = truncate_width(&text, max, "…");
+            assert!(truncated.width() <= max, "{max}: {truncated:?}");
+            let kept = truncated.trim_end_matches('…');
+            assert!(text.starts_with(kept), "{max}: {truncated:?}");
+            assert!(kept.graphemes(true).all(|g| text.contains(g)));
+            assert!(!kept.ends_with('\u{200d}'), "{max}: {truncated:?}");
+        }
+        assert_eq!(truncate_width(&text, 3, "…"), format!("{family}…"));
+    }
+
+    #[test]
+    fn truncate_to_zero_and_one_columns() {
+        assert_eq!(truncate_width("description", 0, "…"), "");
+        assert_eq!(truncate_width("description", 1, "…"), "…");
+        assert_eq!(truncate_width("日本", 1, "…"), "…");
+        assert_eq!(truncate_width("description", 1, ""), "d");
+        assert_eq!(truncate_width("日本", 1, ""), "");
+    }
+
+    #[test]
+    fn truncate_with_ellipsis_wider_than_max() {
+        assert_eq!(truncate_width("description", 2, "..."), "");
+        assert_eq!(truncate_width("description", 3, "..."), "...");
+        assert_eq!(truncate_width("description", 4, "..."), "d...");
+    }
+
+    #[test]
+    fn truncate_keeps_bidi_isolate_closed() {
+        let isolated = bidi_isolate("שלום עולם", BidiMode::Auto);
+        let truncated = truncate_width(&isolated, 4, "…");
+        assert_eq!(truncated, format!("{FSI}שלו…{PDI}"));
+        assert_eq!(truncate_width(&isolated, 0, "…"), format!("{FSI}{PDI}"));
+    }
+
+    #[test]
+    fn sanitize_escapes_control_sequences() {
+        assert_eq!(sanitize("plain text"), "plain text");
+        assert!(matches!(sanitize("plain text"), Cow::Borrowed(_)));
+        // CSI (SGR), OSC 8 with both terminators, and 8-bit CSI
+        assert_eq!(sanitize("\x1b[31mred\x1b[0m"), "\\u{1b}[31mred\\u{1b}[0m");
+        assert_eq!(
+            sanitize("\x1b]8;;https://x\x1b\\link\x1b]8;;\x07"),
+            "\\u{1b}]8;;https://x\\u{1b}\\link\\u{1b}]8;;\\u{7}"
+        );
+        assert_eq!(sanitize("a\u{9b}31mb"), "a\\u{9b}31mb");
+        assert_eq!(sanitize("line\nbreak\ttab\r"), "line\\nbreak\\ttab\\r");
+        assert!(!sanitize("\x1b]2;title\x07").chars().any(char::is_control));
+    }
+
+    #[test]
+    fn bidi_isolates_only_rtl_text_in_auto_mode() {
+        assert_eq!(bidi_isolate("feat", BidiMode::Auto), "feat");
+        assert_eq!(bidi_isolate("תיקון", BidiMode::Auto), format!("{FSI}תיקון{PDI}"));
+        assert_eq!(bidi_isolate("fix إصلاح", BidiMode::Auto), format!("{FSI}fix إصلاح{PDI}"));
+        assert_eq!(bidi_isolate("feat", BidiMode::Always), format!("{FSI}feat{PDI}"));
+        assert_eq!(bidi_isolate("", BidiMode::Always), "");
+        assert_eq!(bidi_isolate("תיקון", BidiMode::Never), "תיקון");
+        // An override character alone still reorders what follows
+        assert_eq!(bidi_isolate("a\u{202e}b", BidiMode::Auto), format!("{FSI}a\u{202e}b{PDI}"));
+    }
+
+    #[test]
+    fn isolates_take_no_columns() {
+        let isolated = bidi_isolate("שלום", BidiMode::Always);
+        assert_eq!(isolated.width(), "שלום".width());
+    }
+}
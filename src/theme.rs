@@ -0,0 +1,639 @@
+//! Color themes for ANSI output
+//!
+//! A theme maps each span role to a text style. `--theme` (or `theme` in
+//! the config) picks one of the built-in palettes:
+//!
+//! ```toml
+//! theme = "dracula"
+//! ```
+//!
+//! On light backgrounds dim text is darkened. The background comes from
+//! `background = "light"` in the config, `JJ_PROMPT_BACKGROUND` (as set by
+//! `jj-prompt background` in the init snippets), or `COLORFGBG`.
+//!
+//! With the default `jj-native` theme, colors the user changed in jj's own
+//! `colors.*` config are applied on top, so the prompt matches `jj log`.
+
+use crate::config::Config;
+use crate::segment::Role;
+use clap::ValueEnum;
+use jj_lib::config::{ConfigGetResultExt, StackedConfig};
+use serde::Deserialize;
+
+/// Clears all colors and attributes
+pub const RESET: &str = "\x1b[0m";
+
+/// Built-in palettes
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// The colors of `jj log`
+    #[default]
+    JjNative,
+    /// Bold and dim change id, color only for problems
+    Minimal,
+    /// Solarized accents
+    Solarized,
+    /// Dracula accents
+    Dracula,
+    /// No colors, only bold, dim and reverse video
+    Monochrome,
+    /// Blue, yellow and orange for red-green (green-weak) color blindness
+    Deuteranopia,
+    /// Like deuteranopia, with errors in orange since red looks dark
+    Protanopia,
+    /// Red, pink and teal for blue-yellow color blindness
+    Tritanopia,
+}
+
+/// Whether the terminal's background is dark or light
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Background {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Background {
+    pub fn name(self) -> &'static str {
+        match self {
+            Background::Dark => "dark",
+            Background::Light => "light",
+        }
+    }
+
+    /// From a color's relative luminance, each channel in 0.0..=1.0
+    pub fn from_rgb(r: f64, g: f64, b: f64) -> Self {
+        if 0.2126 * r + 0.7152 * g + 0.0722 * b > 0.5 {
+            Background::Light
+        } else {
+            Background::Dark
+        }
+    }
+}
+
+/// How many colors the terminal can show
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum ColorDepth {
+    /// The 16 basic colors only
+    #[serde(rename = "16")]
+    Basic,
+    /// The 256-color palette
+    #[serde(rename = "256")]
+    Indexed,
+    /// 24-bit colors
+    #[serde(rename = "truecolor")]
+    Truecolor,
+}
+
+/// A terminal color
+#[derive(Clone, Copy)]
+pub enum Color {
+    /// One of the 16 basic colors, following the terminal's own palette
+    Basic(u8),
+    /// A 256-color palette index, sent as the nearest basic color to
+    /// 16-color terminals
+    Indexed(u8),
+    /// 24-bit color, sent as the nearest color the terminal has
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// A jj color name: `red`, `bright red`, `ansi-color-N`, `#rrggbb`
+    fn from_jj(name: &str) -> Option<Self> {
+        const NAMES: [&str; 8] = [
+            "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+        ];
+        if let Some(hex) = name.strip_prefix('#')
+            && hex.len() == 6
+        {
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        if let Some(index) = name.strip_prefix("ansi-color-") {
+            return index.parse().ok().map(Color::Indexed);
+        }
+        let (bright, name) = match name.strip_prefix("bright ") {
+            Some(name) => (8, name),
+            None => (0, name),
+        };
+        let n = NAMES.iter().position(|&known| known == name)?;
+        Some(Color::Basic(bright + n as u8))
+    }
+
+    /// A starship color: `red`, `purple`, `bright-red`, `0`-`255`, `#rrggbb`
+    fn from_starship(name: &str) -> Option<Self> {
+        if let Ok(index) = name.parse() {
+            return Some(Color::Indexed(index));
+        }
+        let name = name.replace("purple", "magenta");
+        match name.strip_prefix("bright-") {
+            Some(name) => Color::from_jj(&format!("bright {name}")),
+            None => Color::from_jj(&name),
+        }
+    }
+
+    /// SGR parameters for this color as foreground, or background if `bg`,
+    /// reduced to what a terminal of `depth` shows
+    pub fn sgr(self, bg: bool, depth: ColorDepth) -> String {
+        let offset = if bg { 10 } else { 0 };
+        match (self, depth) {
+            (Color::Basic(n @ 0..=7), _) => (30 + offset + n).to_string(),
+            (Color::Basic(n), _) => (90 + offset + (n & 7)).to_string(),
+            (Color::Indexed(n @ 0..=15), ColorDepth::Basic) => Color::Basic(n).sgr(bg, depth),
+            (Color::Indexed(n), ColorDepth::Basic) => {
+                let (r, g, b) = indexed_rgb(n);
+                Color::Basic(basic_color(r, g, b)).sgr(bg, depth)
+            }
+            (Color::Indexed(n), _) => format!("{};5;{n}", 38 + offset),
+            (Color::Rgb(r, g, b), ColorDepth::Truecolor) => {
+                format!("{};2;{r};{g};{b}", 38 + offset)
+            }
+            (Color::Rgb(r, g, b), ColorDepth::Indexed) => {
+                format!("{};5;{}", 38 + offset, nearest_indexed(r, g, b))
+            }
+            (Color::Rgb(r, g, b), ColorDepth::Basic) => {
+                Color::Basic(basic_color(r, g, b)).sgr(bg, depth)
+            }
+        }
+    }
+}
+
+/// Squared distance between two colors
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    [(r1, r2), (g1, g2), (b1, b2)]
+        .iter()
+        .map(|&(a, b)| u32::from(a.abs_diff(b)).pow(2))
+        .sum()
+}
+
+/// The basic color with the same hue, or the closest basic gray for colors
+/// with little of one. Nearest by distance alone would turn most pastels gray.
+fn basic_color(r: u8, g: u8, b: u8) -> u8 {
+    const GRAYS: [(u8, u8); 4] = [(0, 0), (127, 8), (229, 7), (255, 15)];
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    if max - min < 48 {
+        return GRAYS
+            .iter()
+            .min_by_key(|&&(level, _)| distance((r, g, b), (level, level, level)))
+            .map(|&(_, n)| n)
+            .unwrap_or_default();
+    }
+    // Channels above the middle of the range make up the hue
+    let high = |c: u8| u8::from(u16::from(c) * 2 > u16::from(max) + u16::from(min));
+    let hue = high(r) | high(g) << 1 | high(b) << 2;
+    if max >= 230 {
+        hue + 8
+    } else {
+        hue
+    }
+}
+
+/// The color of a 256-color palette index above 15
+fn indexed_rgb(n: u8) -> (u8, u8, u8) {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match n {
+        232.. => {
+            let level = 8 + (n - 232) * 10;
+            (level, level, level)
+        }
+        _ => {
+            let n = usize::from(n.saturating_sub(16));
+            (LEVELS[n / 36], LEVELS[n / 6 % 6], LEVELS[n % 6])
+        }
+    }
+}
+
+/// The closest color in the 256-color palette's 6x6x6 cube or gray ramp
+fn nearest_indexed(r: u8, g: u8, b: u8) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let nearest_level = |c: u8| {
+        (0..6)
+            .min_by_key(|&i| LEVELS[i].abs_diff(c))
+            .unwrap_or_default()
+    };
+    let distance = |color| distance((r, g, b), color);
+
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube = (LEVELS[ri], LEVELS[gi], LEVELS[bi]);
+    // Gray ramp: 24 steps from 8 to 238
+    let average = (u32::from(r) + u32::from(g) + u32::from(b)) / 3;
+    let step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let level = 8 + step * 10;
+    if distance((level, level, level)) < distance(cube) {
+        232 + step
+    } else {
+        16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8
+    }
+}
+
+/// Colors and attributes for one role
+#[derive(Clone, Copy, Default)]
+pub struct TextStyle {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub reverse: bool,
+}
+
+impl TextStyle {
+    const PLAIN: TextStyle = TextStyle {
+        fg: None,
+        bg: None,
+        bold: false,
+        dim: false,
+        italic: false,
+        underline: false,
+        reverse: false,
+    };
+    const BOLD: TextStyle = TextStyle {
+        bold: true,
+        ..TextStyle::PLAIN
+    };
+    const DIM: TextStyle = TextStyle {
+        dim: true,
+        ..TextStyle::PLAIN
+    };
+
+    const fn fg(color: Color) -> Self {
+        TextStyle {
+            fg: Some(color),
+            ..TextStyle::PLAIN
+        }
+    }
+
+    const fn bold_fg(color: Color) -> Self {
+        TextStyle {
+            bold: true,
+            ..TextStyle::fg(color)
+        }
+    }
+
+    const fn on(fg: Color, bg: Color) -> Self {
+        TextStyle {
+            bg: Some(bg),
+            ..TextStyle::fg(fg)
+        }
+    }
+
+    /// The SGR escape sequence for this style, empty if it's plain
+    pub fn sgr(&self, depth: ColorDepth) -> String {
+        let mut params = Vec::new();
+        if self.bold {
+            params.push("1".to_string());
+        }
+        if self.dim {
+            params.push("2".to_string());
+        }
+        if self.italic {
+            params.push("3".to_string());
+        }
+        if self.underline {
+            params.push("4".to_string());
+        }
+        if self.reverse {
+            params.push("7".to_string());
+        }
+        params.extend(self.fg.map(|color| color.sgr(false, depth)));
+        params.extend(self.bg.map(|color| color.sgr(true, depth)));
+        if params.is_empty() {
+            return String::new();
+        }
+        format!("\x1b[{}m", params.join(";"))
+    }
+}
+
+/// A style in the config, in the format of jj's `colors.<label>`: a color
+/// name or a table. Strings that aren't jj colors are read as starship style
+/// strings (`bold purple`), so styles can be copied from `starship.toml`.
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum StyleSpec {
+    Fg(String),
+    Table {
+        fg: Option<String>,
+        bg: Option<String>,
+        bold: Option<bool>,
+        dim: Option<bool>,
+        italic: Option<bool>,
+        underline: Option<bool>,
+        reverse: Option<bool>,
+    },
+}
+
+impl TextStyle {
+    /// Layer `spec` over this style, as jj does for nested labels.
+    /// `default` clears a color; unknown colors are ignored.
+    fn apply(&mut self, spec: StyleSpec) {
+        let color = |name: &str| match name {
+            "default" => Some(None),
+            name => Color::from_jj(name).map(Some),
+        };
+        match spec {
+            StyleSpec::Fg(spec) => match color(&spec) {
+                Some(fg) => self.fg = fg,
+                None => self.apply_starship(&spec),
+            },
+            StyleSpec::Table {
+                fg,
+                bg,
+                bold,
+                dim,
+                italic,
+                underline,
+                reverse,
+            } => {
+                if let Some(fg) = fg.as_deref().and_then(color) {
+                    self.fg = fg;
+                }
+                if let Some(bg) = bg.as_deref().and_then(color) {
+                    self.bg = bg;
+                }
+                self.bold = bold.unwrap_or(self.bold);
+                self.dim = dim.unwrap_or(self.dim);
+                self.italic = italic.unwrap_or(self.italic);
+                self.underline = underline.unwrap_or(self.underline);
+                self.reverse = reverse.unwrap_or(self.reverse);
+            }
+        }
+    }
+
+    /// Layer a starship style string over this style: `bold`, `italic`,
+    /// `underline`, `dimmed`, `inverted`, `none`, and colors, bare or as
+    /// `fg:<color>` / `bg:<color>`. Unknown words are ignored, as starship
+    /// does.
+    fn apply_starship(&mut self, spec: &str) {
+        let color = |name: &str| match name {
+            "none" => Some(None),
+            name => Color::from_starship(name).map(Some),
+        };
+        for word in spec.to_lowercase().split_whitespace() {
+            match word {
+                "none" => *self = TextStyle::PLAIN,
+                "bold" => self.bold = true,
+                "dimmed" => self.dim = true,
+                "italic" => self.italic = true,
+                "underline" => self.underline = true,
+                "inverted" => self.reverse = true,
+                _ => {
+                    if let Some(bg) = word.strip_prefix("bg:") {
+                        self.bg = color(bg).unwrap_or(self.bg);
+                    } else {
+                        let fg = word.strip_prefix("fg:").unwrap_or(word);
+                        self.fg = color(fg).unwrap_or(self.fg);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A style for every colored role
+pub struct Palette {
+    pub symbol: TextStyle,
+    pub change_id_prefix: TextStyle,
+    pub change_id_rest: TextStyle,
+    pub bookmark: TextStyle,
+    pub dim: TextStyle,
+    pub warning: TextStyle,
+    pub success: TextStyle,
+    pub error: TextStyle,
+    /// Drawn as a chip, so it should have a background
+    pub topic: TextStyle,
+    pub ticket: TextStyle,
+}
+
+impl Palette {
+    /// Restyle the roles whose jj labels the user recolored under `colors`,
+    /// each label layered over the more general ones before it
+    pub fn apply_jj_colors(&mut self, jj_config: &StackedConfig) {
+        let roles: [(&mut TextStyle, &[&str]); 4] = [
+            (
+                &mut self.change_id_prefix,
+                &["change_id", "prefix", "change_id prefix"],
+            ),
+            (&mut self.change_id_rest, &["rest", "change_id rest"]),
+            (&mut self.bookmark, &["bookmarks", "bookmark"]),
+            (&mut self.error, &["conflict"]),
+        ];
+        for (style, labels) in roles {
+            for label in labels {
+                if let Ok(Some(spec)) = jj_config.get::<StyleSpec>(["colors", label]).optional() {
+                    style.apply(spec);
+                }
+            }
+        }
+    }
+
+    /// The style spans of `role` are drawn in
+    pub fn get(&self, role: Role) -> TextStyle {
+        match role {
+            Role::Plain => TextStyle::PLAIN,
+            Role::Symbol => self.symbol,
+            Role::ChangeIdPrefix => self.change_id_prefix,
+            Role::ChangeIdRest => self.change_id_rest,
+            Role::Bookmark => self.bookmark,
+            Role::Dim => self.dim,
+            Role::Warning => self.warning,
+            Role::Success => self.success,
+            Role::Error => self.error,
+            Role::Topic => self.topic,
+            Role::Ticket => self.ticket,
+        }
+    }
+}
+
+/// Per-segment styles from `[colors]` in the config, layered over the
+/// theme's style for each span:
+///
+/// ```toml
+/// [colors]
+/// description = "blue"
+/// change-id-prefix = { fg = "red", bold = true }
+/// bookmarks = "bold purple"
+/// ```
+#[derive(Default)]
+pub struct Overrides(Vec<(&'static str, StyleSpec)>);
+
+impl Overrides {
+    /// Every overridable part of the prompt, as `colors.<key>`
+    const KEYS: [&'static str; 23] = [
+        "colors.symbol",
+        "colors.change-id-prefix",
+        "colors.change-id-rest",
+        "colors.plugins",
+        "colors.bookmarks",
+        "colors.topic",
+        "colors.ticket",
+        "colors.merge",
+        "colors.stack-depth",
+        "colors.behind-trunk",
+        "colors.private",
+        "colors.status",
+        "colors.signature",
+        "colors.author",
+        "colors.stack-conflicts",
+        "colors.counters",
+        "colors.stale-bookmarks",
+        "colors.dirty",
+        "colors.file-count",
+        "colors.untracked",
+        "colors.age",
+        "colors.description",
+        "colors.parent",
+    ];
+
+    pub fn load(config: &Config) -> Self {
+        let specs = Overrides::KEYS.iter().filter_map(|&key| {
+            let spec = config.get(key)?;
+            Some((key.trim_start_matches("colors."), spec))
+        });
+        Overrides(specs.collect())
+    }
+
+    /// `style` with the override for `key` layered on top, if there is one
+    pub fn apply(&self, key: &str, mut style: TextStyle) -> TextStyle {
+        if let Some((_, spec)) = self.0.iter().find(|(k, _)| *k == key) {
+            style.apply(spec.clone());
+        }
+        style
+    }
+}
+
+impl Theme {
+    /// The palette, with dim text darkened on light backgrounds where
+    /// faint gray would be unreadable
+    pub fn palette(self, background: Background) -> Palette {
+        use Color::{Basic, Indexed, Rgb};
+        let mut palette = match self {
+            Theme::JjNative => Palette {
+                symbol: TextStyle::fg(Basic(2)),
+                change_id_prefix: TextStyle::bold_fg(Indexed(5)),
+                change_id_rest: TextStyle::fg(Indexed(8)),
+                bookmark: TextStyle::fg(Indexed(5)),
+                dim: TextStyle::DIM,
+                warning: TextStyle::fg(Basic(3)),
+                success: TextStyle::fg(Basic(2)),
+                error: TextStyle::fg(Basic(1)),
+                topic: TextStyle::on(Basic(0), Basic(6)),
+                ticket: TextStyle::fg(Basic(4)),
+            },
+            Theme::Minimal => Palette {
+                symbol: TextStyle::PLAIN,
+                change_id_prefix: TextStyle::BOLD,
+                change_id_rest: TextStyle::DIM,
+                bookmark: TextStyle::PLAIN,
+                dim: TextStyle::DIM,
+                warning: TextStyle::fg(Basic(3)),
+                success: TextStyle::DIM,
+                error: TextStyle::fg(Basic(1)),
+                topic: TextStyle {
+                    reverse: true,
+                    ..TextStyle::DIM
+                },
+                ticket: TextStyle::PLAIN,
+            },
+            Theme::Solarized => Palette {
+                symbol: TextStyle::fg(Rgb(0x85, 0x99, 0x00)),
+                change_id_prefix: TextStyle::bold_fg(Rgb(0xd3, 0x36, 0x82)),
+                change_id_rest: TextStyle::fg(Rgb(0x58, 0x6e, 0x75)),
+                bookmark: TextStyle::fg(Rgb(0x6c, 0x71, 0xc4)),
+                dim: TextStyle::fg(Rgb(0x65, 0x7b, 0x83)),
+                warning: TextStyle::fg(Rgb(0xb5, 0x89, 0x00)),
+                success: TextStyle::fg(Rgb(0x85, 0x99, 0x00)),
+                error: TextStyle::fg(Rgb(0xdc, 0x32, 0x2f)),
+                topic: TextStyle::on(Rgb(0xfd, 0xf6, 0xe3), Rgb(0x2a, 0xa1, 0x98)),
+                ticket: TextStyle::fg(Rgb(0x26, 0x8b, 0xd2)),
+            },
+            Theme::Dracula => Palette {
+                symbol: TextStyle::fg(Rgb(0x50, 0xfa, 0x7b)),
+                change_id_prefix: TextStyle::bold_fg(Rgb(0xff, 0x79, 0xc6)),
+                change_id_rest: TextStyle::fg(Rgb(0x62, 0x72, 0xa4)),
+                bookmark: TextStyle::fg(Rgb(0xbd, 0x93, 0xf9)),
+                dim: TextStyle::fg(Rgb(0x62, 0x72, 0xa4)),
+                warning: TextStyle::fg(Rgb(0xf1, 0xfa, 0x8c)),
+                success: TextStyle::fg(Rgb(0x50, 0xfa, 0x7b)),
+                error: TextStyle::fg(Rgb(0xff, 0x55, 0x55)),
+                topic: TextStyle::on(Rgb(0x28, 0x2a, 0x36), Rgb(0x8b, 0xe9, 0xfd)),
+                ticket: TextStyle::fg(Rgb(0x8b, 0xe9, 0xfd)),
+            },
+            Theme::Monochrome => Palette {
+                symbol: TextStyle::PLAIN,
+                change_id_prefix: TextStyle::BOLD,
+                change_id_rest: TextStyle::PLAIN,
+                bookmark: TextStyle::BOLD,
+                dim: TextStyle::DIM,
+                warning: TextStyle::BOLD,
+                success: TextStyle::PLAIN,
+                error: TextStyle {
+                    reverse: true,
+                    ..TextStyle::BOLD
+                },
+                topic: TextStyle {
+                    reverse: true,
+                    ..TextStyle::PLAIN
+                },
+                ticket: TextStyle::PLAIN,
+            },
+            // From the Okabe-Ito palette, which stays distinct under each
+            // kind of color blindness
+            Theme::Deuteranopia | Theme::Protanopia => Palette {
+                symbol: TextStyle::fg(Rgb(0x56, 0xb4, 0xe9)),
+                change_id_prefix: TextStyle::bold_fg(Rgb(0xcc, 0x79, 0xa7)),
+                change_id_rest: TextStyle::fg(Indexed(8)),
+                bookmark: TextStyle::fg(Rgb(0xcc, 0x79, 0xa7)),
+                dim: TextStyle::DIM,
+                warning: TextStyle::fg(Rgb(0xf0, 0xe4, 0x42)),
+                success: TextStyle::fg(Rgb(0x56, 0xb4, 0xe9)),
+                error: if self == Theme::Protanopia {
+                    TextStyle::bold_fg(Rgb(0xe6, 0x9f, 0x00))
+                } else {
+                    TextStyle::bold_fg(Rgb(0xd5, 0x5e, 0x00))
+                },
+                topic: TextStyle::on(Basic(0), Rgb(0x56, 0xb4, 0xe9)),
+                ticket: TextStyle::fg(Rgb(0x00, 0x72, 0xb2)),
+            },
+            Theme::Tritanopia => Palette {
+                symbol: TextStyle::fg(Rgb(0x00, 0x9e, 0x73)),
+                change_id_prefix: TextStyle::bold_fg(Rgb(0x56, 0xb4, 0xe9)),
+                change_id_rest: TextStyle::fg(Indexed(8)),
+                bookmark: TextStyle::fg(Rgb(0x56, 0xb4, 0xe9)),
+                dim: TextStyle::DIM,
+                warning: TextStyle::fg(Rgb(0xcc, 0x79, 0xa7)),
+                success: TextStyle::fg(Rgb(0x00, 0x9e, 0x73)),
+                error: TextStyle::bold_fg(Rgb(0xd5, 0x5e, 0x00)),
+                topic: TextStyle::on(Basic(0), Rgb(0x00, 0x9e, 0x73)),
+                ticket: TextStyle::fg(Rgb(0x56, 0xb4, 0xe9)),
+            },
+        };
+        if background == Background::Light {
+            match self {
+                Theme::JjNative | Theme::Minimal => {
+                    palette.dim = TextStyle::fg(Indexed(241));
+                    palette.change_id_rest = TextStyle::fg(Indexed(244));
+                    if self == Theme::Minimal {
+                        palette.success = palette.dim;
+                    } else {
+                        palette.warning = TextStyle::fg(Indexed(136));
+                    }
+                }
+                // Solarized's secondary content color for light mode
+                Theme::Solarized => {
+                    palette.dim = TextStyle::fg(Rgb(0x93, 0xa1, 0xa1));
+                    palette.change_id_rest = palette.dim;
+                }
+                // Yellow is unreadable on white
+                Theme::Deuteranopia | Theme::Protanopia => {
+                    palette.warning = TextStyle::fg(Rgb(0x9a, 0x80, 0x00));
+                }
+                Theme::Dracula | Theme::Monochrome | Theme::Tritanopia => {}
+            }
+        }
+        palette
+    }
+}
@@ -0,0 +1,119 @@
+//! Minimums below which a segment stays hidden
+//!
+//! Counts are shown from their threshold up, and the age (with `--age`) once
+//! the commit is at least that old, so the prompt stays quiet until something
+//! is worth noticing:
+//!
+//! ```toml
+//! [thresholds]
+//! file-count = 3  # show `~N` from 3 changed files
+//! age = "6h"      # a duration (`45m`, `6h`, `2d`, `1w`) or seconds (`"90"`)
+//! ```
+//!
+//! The file count can also turn from dim to yellow to red as a change grows
+//! too big to review:
+//!
+//! ```toml
+//! [thresholds]
+//! file-count-warning = 10
+//! file-count-error = 50
+//! ```
+
+use crate::config::Config;
+use crate::debug;
+use crate::segment::Role;
+use serde::Deserialize;
+
+/// Smallest value of each segment that is shown; zero shows everything
+#[derive(Default)]
+pub struct Thresholds {
+    pub file_count: usize,
+    pub untracked: usize,
+    pub stack_depth: usize,
+    pub behind_trunk: usize,
+    pub private: usize,
+    pub stack_conflicts: usize,
+    pub stale_bookmarks: usize,
+    /// In seconds
+    pub age: i64,
+    /// File count from which `~N` is drawn as a warning
+    pub file_count_warning: Option<usize>,
+    /// File count from which `~N` is drawn as an error
+    pub file_count_error: Option<usize>,
+}
+
+/// A duration in the config: seconds, or a number with a unit
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConfigDuration {
+    Seconds(i64),
+    Text(String),
+}
+
+impl Thresholds {
+    pub fn load(config: &Config) -> Self {
+        let mut thresholds = Thresholds::default();
+        let counts = [
+            ("thresholds.file-count", &mut thresholds.file_count),
+            ("thresholds.untracked", &mut thresholds.untracked),
+            ("thresholds.stack-depth", &mut thresholds.stack_depth),
+            ("thresholds.behind-trunk", &mut thresholds.behind_trunk),
+            ("thresholds.private", &mut thresholds.private),
+            (
+                "thresholds.stack-conflicts",
+                &mut thresholds.stack_conflicts,
+            ),
+            (
+                "thresholds.stale-bookmarks",
+                &mut thresholds.stale_bookmarks,
+            ),
+        ];
+        for (key, threshold) in counts {
+            if let Some(value) = config.get(key) {
+                *threshold = value;
+            }
+        }
+        thresholds.file_count_warning = config.get("thresholds.file-count-warning");
+        thresholds.file_count_error = config.get("thresholds.file-count-error");
+        thresholds.age = match config.get("thresholds.age") {
+            Some(ConfigDuration::Seconds(seconds)) => seconds,
+            Some(ConfigDuration::Text(text)) => parse_duration(&text).unwrap_or_else(|| {
+                debug::log("read thresholds.age", format!("not a duration: {text:?}"));
+                0
+            }),
+            None => 0,
+        };
+        thresholds
+    }
+
+    /// Color of `~N` for `count` changed files
+    pub fn file_count_role(&self, count: usize) -> Role {
+        let reached = |threshold: Option<usize>| threshold.is_some_and(|n| count >= n);
+        if reached(self.file_count_error) {
+            Role::Error
+        } else if reached(self.file_count_warning) {
+            Role::Warning
+        } else {
+            Role::Dim
+        }
+    }
+}
+
+/// `45m`, `6h`, `2d`, `1w`, `1y` (or `30s`, or a bare `30`) in seconds
+fn parse_duration(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let unit = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(number.parse::<i64>().ok()? * unit)
+}
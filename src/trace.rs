@@ -0,0 +1,34 @@
+//! Trace log: `--log-file <PATH>` or `$JJ_PROMPT_LOG`
+//!
+//! Every invocation appends its tracing events to the file, timestamped: a
+//! span for the run with its working directory and arguments, the phases
+//! `--profile` reports, the failures `--debug` reports, and the spans jj-lib
+//! records while loading the repo. Spans end with their duration, so a slow
+//! prompt can be traced back to its repo and step after the fact.
+
+use std::env;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// The log file from `--log-file`, else `$JJ_PROMPT_LOG`
+pub fn path(flag: Option<&Path>) -> Option<PathBuf> {
+    flag.map(Path::to_path_buf)
+        .or_else(|| env::var_os("JJ_PROMPT_LOG").map(PathBuf::from))
+        .filter(|path| !path.as_os_str().is_empty())
+}
+
+/// Send tracing events to the end of `path`. A log that can't be opened is
+/// ignored; the prompt matters more.
+pub fn init(path: &Path) {
+    let Ok(file) = OpenOptions::new().create(true).append(true).open(path) else {
+        return;
+    };
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .try_init()
+        .ok();
+}
@@ -0,0 +1,25 @@
+//! `--if-changed`: print nothing when the prompt is the one printed last time
+//!
+//! A hash of the last prompt for the same repo and flags is kept in the state
+//! directory. When the new prompt hashes the same, nothing is printed and the
+//! exit status is 5, so transient prompts and status bars can skip the
+//! redraw.
+
+use crate::state::fnv1a;
+use std::fs;
+use std::path::Path;
+
+/// Whether `output` is what was last rendered for `key`, remembering it for
+/// next time
+pub fn check(dir: &Path, key: u64, output: &str) -> bool {
+    let last = dir.join(format!("last-{key:016x}"));
+    let hash = format!("{:016x}", fnv1a(output.as_bytes()));
+    if fs::read_to_string(&last).is_ok_and(|last| last == hash) {
+        return true;
+    }
+    // A torn write only costs one redraw
+    if fs::create_dir_all(dir).is_ok() {
+        fs::write(&last, hash).ok();
+    }
+    false
+}
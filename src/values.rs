@@ -0,0 +1,132 @@
+//! The prompt's raw values, for hosts that consume structured output
+//!
+//! Every JSON output carries `schema_version`. Within a version, fields are
+//! only ever added: renaming or removing one, or changing its type or
+//! meaning, bumps [`SCHEMA_VERSION`]. Fields are always present, null when
+//! there's nothing to show, so consumers don't have to tell a missing field
+//! from an empty one.
+
+use crate::counters::Count;
+use crate::parent::ParentInfo;
+use crate::plugins::PluginOutput;
+use crate::text::format_age;
+use crate::PromptInfo;
+use jj_lib::signing::SigStatus;
+use serde::Serialize;
+
+/// Version of the JSON outputs' structure; see the module docs
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Working copy state, most severe first, for hosts that style by it
+#[derive(Clone, Copy)]
+pub enum State {
+    Conflict,
+    Divergent,
+    Modified,
+    Clean,
+}
+
+impl State {
+    pub fn of(info: &PromptInfo) -> Self {
+        if info.has_conflict {
+            State::Conflict
+        } else if info.divergent_count.is_some() {
+            State::Divergent
+        } else if info.dirty || info.file_count.unwrap_or(0) + info.untracked_count.unwrap_or(0) > 0
+        {
+            State::Modified
+        } else {
+            State::Clean
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            State::Conflict => "conflict",
+            State::Divergent => "divergent",
+            State::Modified => "modified",
+            State::Clean => "clean",
+        }
+    }
+}
+
+/// Everything the prompt shows, unformatted
+#[derive(Serialize)]
+pub struct Values<'a> {
+    schema_version: u32,
+    change_id: &'a str,
+    plugins: &'a [PluginOutput],
+    bookmarks: &'a [String],
+    description: &'a str,
+    description_fallback: Option<&'a str>,
+    topic: Option<&'a str>,
+    ticket: Option<&'a str>,
+    ticket_url: Option<&'a str>,
+    merge_parents: Option<usize>,
+    merge_is_parent: bool,
+    conflict: bool,
+    conflict_count: usize,
+    divergent_count: Option<usize>,
+    stack_depth: Option<usize>,
+    behind_trunk: Option<usize>,
+    private_count: Option<usize>,
+    stack_conflict_count: Option<usize>,
+    stale_bookmark_count: Option<usize>,
+    counters: &'a [Count],
+    dirty: bool,
+    file_count: Option<usize>,
+    untracked_count: Option<usize>,
+    signature: Option<&'static str>,
+    author_mismatch: bool,
+    age_seconds: i64,
+    age: String,
+    parent: Option<&'a ParentInfo>,
+    is_parent: bool,
+}
+
+impl<'a> Values<'a> {
+    pub fn new(info: &'a PromptInfo) -> Self {
+        let (ticket, ticket_url) = match &info.ticket {
+            Some((ticket, url)) => (Some(ticket.as_str()), url.as_deref()),
+            None => (None, None),
+        };
+        Values {
+            schema_version: SCHEMA_VERSION,
+            change_id: &info.change_id,
+            plugins: &info.plugins,
+            bookmarks: &info.bookmarks,
+            description: &info.description,
+            description_fallback: info
+                .description_fallback
+                .as_ref()
+                .map(|(_, text)| text.as_str()),
+            topic: info.topic.as_deref(),
+            ticket,
+            ticket_url,
+            merge_parents: info.merge_parent_count,
+            merge_is_parent: info.merge_is_parent,
+            conflict: info.has_conflict,
+            conflict_count: info.conflict_count,
+            divergent_count: info.divergent_count,
+            stack_depth: info.stack_depth,
+            behind_trunk: info.behind_trunk,
+            private_count: info.private_count,
+            stack_conflict_count: info.stack_conflict_count,
+            stale_bookmark_count: info.stale_bookmark_count,
+            counters: &info.counters,
+            dirty: info.dirty,
+            file_count: info.file_count,
+            untracked_count: info.untracked_count,
+            signature: info.signature.map(|status| match status {
+                SigStatus::Good => "good",
+                SigStatus::Bad => "bad",
+                SigStatus::Unknown => "unknown",
+            }),
+            author_mismatch: info.author_mismatch,
+            age_seconds: info.age_secs,
+            age: format_age(info.age_secs),
+            parent: info.parent.as_ref(),
+            is_parent: info.is_parent,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! JSON for a Waybar custom module
+//!
+//! `--output waybar` prints the plain prompt as `text`, a summary of the
+//! change as `tooltip`, and the working copy state (`conflict`, `divergent`,
+//! `modified`, `clean`) as `class` and `alt`, so the bar's CSS and
+//! `format-icons` can style by it. Text is escaped for Waybar's Pango markup.
+
+use crate::text::escape_markup;
+use crate::values::State;
+use crate::PromptInfo;
+use serde_json::json;
+
+/// `info` and the rendered `text` as a Waybar module update
+pub fn encode(info: &PromptInfo, text: &str) -> String {
+    let state = State::of(info).name();
+    json!({
+        "text": escape_markup(text.trim()),
+        "tooltip": escape_markup(&tooltip(info)),
+        "class": state,
+        "alt": state,
+    })
+    .to_string()
+}
+
+/// The change id and bookmarks, the description, then what needs attention
+fn tooltip(info: &PromptInfo) -> String {
+    let mut heading = info.change_id.clone();
+    for bookmark in &info.bookmarks {
+        heading.push(' ');
+        heading.push_str(bookmark);
+    }
+    let description = match &info.description_fallback {
+        _ if !info.description.is_empty() => info.description.as_str(),
+        Some((_, fallback)) => fallback.as_str(),
+        None => "(no description set)",
+    };
+    let mut lines = vec![heading, description.to_string()];
+    if info.has_conflict {
+        lines.push(format!("{} conflicted", count(info.conflict_count, "file")));
+    }
+    if let Some(divergent) = info.divergent_count {
+        lines.push(format!("divergent, {} visible", count(divergent, "commit")));
+    }
+    if let Some(files) = info.file_count {
+        lines.push(format!("{} changed", count(files, "file")));
+    }
+    if let Some(behind) = info.behind_trunk {
+        lines.push(format!("{} behind trunk", count(behind, "commit")));
+    }
+    lines.join("\n")
+}
+
+/// `n` and `noun`, plural unless `n` is 1
+fn count(n: usize, noun: &str) -> String {
+    match n {
+        1 => format!("1 {noun}"),
+        n => format!("{n} {noun}s"),
+    }
+}
@@ -0,0 +1,312 @@
+//! Working copy inspection that doesn't require a snapshot, and the snapshot
+//! itself for `--snapshot`
+
+use crate::config;
+use jj_lib::fileset::{self, FilesetDiagnostics};
+use jj_lib::git;
+use jj_lib::gitignore::GitIgnoreFile;
+use jj_lib::local_working_copy::{FileStates, LocalWorkingCopy};
+use jj_lib::matchers::{Matcher, NothingMatcher};
+use jj_lib::repo::{ReadonlyRepo, Repo};
+use jj_lib::repo_path::{RepoPath, RepoPathUiConverter};
+use jj_lib::settings::{HumanByteSize, UserSettings};
+use jj_lib::working_copy::{SnapshotOptions, WorkingCopyFreshness};
+use jj_lib::workspace::Workspace;
+use pollster::FutureExt as _;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Directories jj never snapshots (same as jj-lib's reserved names)
+const RESERVED_DIR_NAMES: &[&str] = &[".git", ".jj"];
+
+/// Which new files a snapshot starts tracking, from jj's `snapshot.*`
+/// settings
+struct NewFiles {
+    /// `snapshot.auto-track`
+    matcher: Box<dyn Matcher>,
+    /// `snapshot.max-new-file-size`, in bytes
+    max_size: u64,
+}
+
+impl NewFiles {
+    fn from_settings(settings: &UserSettings, root: &Path) -> Option<Self> {
+        // jj-cli's defaults, which aren't part of jj-lib's
+        let auto_track = settings
+            .get_string("snapshot.auto-track")
+            .unwrap_or_else(|_| "all()".to_string());
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: root.to_path_buf(),
+            base: root.to_path_buf(),
+        };
+        let matcher = fileset::parse(&mut FilesetDiagnostics::new(), &auto_track, &path_converter)
+            .ok()?
+            .to_matcher();
+        let max_size =
+            match settings.get_value_with("snapshot.max-new-file-size", HumanByteSize::try_from) {
+                Ok(HumanByteSize(0)) => u64::MAX,
+                Ok(HumanByteSize(size)) => size,
+                Err(_) => 1024 * 1024,
+            };
+        Some(NewFiles { matcher, max_size })
+    }
+
+    /// Whether a snapshot would start tracking the file at `path`
+    fn tracks(&self, path: &RepoPath, disk_path: &Path) -> bool {
+        self.matcher.matches(path)
+            && fs::symlink_metadata(disk_path).is_ok_and(|meta| meta.len() <= self.max_size)
+    }
+}
+
+/// Count files on disk that the last snapshot doesn't know about, that
+/// aren't ignored, and that `snapshot.auto-track` and
+/// `snapshot.max-new-file-size` let in, i.e. files the next jj command will
+/// start tracking. If jj uses watchman, only the files it reports changed
+/// since the snapshot are checked, instead of walking the whole working copy.
+pub fn count_untracked(workspace: &Workspace, settings: &UserSettings) -> Option<usize> {
+    let wc = workspace
+        .working_copy()
+        .downcast_ref::<LocalWorkingCopy>()?;
+    let file_states = wc.file_states().ok()?;
+    let root = workspace.workspace_root();
+    let new_files = NewFiles::from_settings(settings, root)?;
+
+    let mut ignores = GitIgnoreFile::empty();
+    for path in base_ignore_files(root) {
+        ignores = ignores.chain_with_file("", path).ok()?;
+    }
+
+    if let Some(changed) = watchman_changes(wc, settings) {
+        let count = changed
+            .iter()
+            .filter(|path| is_untracked(root, path, &file_states, &ignores, &new_files))
+            .count();
+        return Some(count);
+    }
+
+    let mut count = 0;
+    visit_dir(root, "", &file_states, &ignores, &new_files, &mut count);
+    Some(count)
+}
+
+/// Files changed since the last snapshot, if jj's fsmonitor is watchman and
+/// it can tell (a fresh watchman instance can't)
+#[cfg(feature = "watchman")]
+fn watchman_changes(wc: &LocalWorkingCopy, settings: &UserSettings) -> Option<Vec<PathBuf>> {
+    use jj_lib::fsmonitor::FsmonitorSettings;
+
+    let FsmonitorSettings::Watchman(config) = FsmonitorSettings::from_settings(settings).ok()?
+    else {
+        return None;
+    };
+    let (_clock, changed) = wc.query_watchman(&config).ok()?;
+    changed
+}
+
+#[cfg(not(feature = "watchman"))]
+fn watchman_changes(_wc: &LocalWorkingCopy, _settings: &UserSettings) -> Option<Vec<PathBuf>> {
+    None
+}
+
+/// Whether `path` (relative to `root`) is a file the last snapshot doesn't
+/// know about and the next one will track, outside nested repos and ignored
+/// directories
+fn is_untracked(
+    root: &Path,
+    path: &Path,
+    file_states: &FileStates,
+    ignores: &Arc<GitIgnoreFile>,
+    new_files: &NewFiles,
+) -> bool {
+    let mut names = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => match name.to_str() {
+                Some(name) => names.push(name),
+                None => return false,
+            },
+            _ => return false,
+        }
+    }
+    let Some((file_name, dirs)) = names.split_last() else {
+        return false;
+    };
+    if !root.join(path).is_file() {
+        return false;
+    }
+
+    let mut ignores = ignores.clone();
+    let mut prefix = String::new();
+    let mut dir = root.to_path_buf();
+    for name in dirs {
+        ignores = ignores
+            .chain_with_file(&prefix, dir.join(".gitignore"))
+            .unwrap_or_else(|_| ignores.clone());
+        dir.push(name);
+        prefix = format!("{prefix}{name}/");
+        let is_nested_repo = RESERVED_DIR_NAMES
+            .iter()
+            .any(|name| dir.join(name).symlink_metadata().is_ok());
+        if RESERVED_DIR_NAMES.contains(name) || is_nested_repo || ignores.matches(&prefix) {
+            return false;
+        }
+    }
+    let ignores = ignores
+        .chain_with_file(&prefix, dir.join(".gitignore"))
+        .unwrap_or_else(|_| ignores.clone());
+
+    let path = format!("{prefix}{file_name}");
+    RepoPath::from_internal_string(&path).is_ok_and(|repo_path| {
+        !file_states.contains_path(repo_path)
+            && !ignores.matches(&path)
+            && new_files.tracks(repo_path, &root.join(&path))
+    })
+}
+
+/// Snapshot the working copy like any jj command would, returning the repo
+/// at the resulting operation. `None` if the working copy is stale or
+/// locked, or the snapshot fails.
+pub fn snapshot(workspace: &mut Workspace, repo: &Arc<ReadonlyRepo>) -> Option<Arc<ReadonlyRepo>> {
+    let root = workspace.workspace_root().to_path_buf();
+    let new_files = NewFiles::from_settings(repo.settings(), &root)?;
+    let mut base_ignores = GitIgnoreFile::empty();
+    for path in base_ignore_files(&root) {
+        base_ignores = base_ignores.chain_with_file("", path).ok()?;
+    }
+    let options = SnapshotOptions {
+        base_ignores,
+        progress: None,
+        start_tracking_matcher: new_files.matcher.as_ref(),
+        force_tracking_matcher: &NothingMatcher,
+        max_new_file_size: new_files.max_size,
+    };
+
+    let colocated = is_colocated(&root, repo);
+    let workspace_name = workspace.workspace_name().to_owned();
+    let mut locked_ws = workspace.start_working_copy_mutation().ok()?;
+    let wc_id = repo.view().get_wc_commit_id(&workspace_name)?;
+    let wc_commit = repo.store().get_commit(wc_id).ok()?;
+    let (repo, wc_commit) =
+        match WorkingCopyFreshness::check_stale(locked_ws.locked_wc(), &wc_commit, repo).ok()? {
+            WorkingCopyFreshness::Fresh => (repo.clone(), wc_commit),
+            // Another jj process snapshotted meanwhile
+            WorkingCopyFreshness::Updated(op) => {
+                let repo = repo.reload_at(&op).ok()?;
+                let wc_id = repo.view().get_wc_commit_id(&workspace_name)?;
+                let wc_commit = repo.store().get_commit(wc_id).ok()?;
+                (repo, wc_commit)
+            }
+            // Left for `jj workspace update-stale` to sort out
+            WorkingCopyFreshness::WorkingCopyStale | WorkingCopyFreshness::SiblingOperation => {
+                return None;
+            }
+        };
+
+    let (new_tree, _stats) = locked_ws.locked_wc().snapshot(&options).block_on().ok()?;
+    let repo = if new_tree.tree_ids() == wc_commit.tree_ids() {
+        repo
+    } else {
+        let mut tx = repo.start_transaction();
+        tx.set_is_snapshot(true);
+        let mut_repo = tx.repo_mut();
+        let commit = mut_repo
+            .rewrite_commit(&wc_commit)
+            .set_tree(new_tree)
+            .write()
+            .ok()?;
+        mut_repo
+            .set_wc_commit(workspace_name, commit.id().clone())
+            .ok()?;
+        mut_repo.rebase_descendants().ok()?;
+        // Keep git's index and refs in step, as jj does in colocated repos
+        if colocated {
+            git::update_intent_to_add(
+                mut_repo.base_repo().as_ref(),
+                &wc_commit.tree(),
+                &commit.tree(),
+            )
+            .ok()?;
+            git::export_refs(mut_repo).ok()?;
+        }
+        tx.commit("snapshot working copy").ok()?
+    };
+    locked_ws.finish(repo.op_id().clone()).ok()?;
+    Some(repo)
+}
+
+/// Whether the workspace at `root` is also git's working tree
+fn is_colocated(root: &Path, repo: &ReadonlyRepo) -> bool {
+    let Some(git_workdir) = git::get_git_backend(repo.store())
+        .ok()
+        .and_then(|backend| backend.git_workdir().map(Path::to_path_buf))
+    else {
+        return false;
+    };
+    git_workdir == root
+        || root
+            .join(".git")
+            .canonicalize()
+            .is_ok_and(|dot_git| git_workdir.canonicalize().ok().as_deref() == dot_git.parent())
+}
+
+/// Global and repo-local git excludes, which jj honors as well
+fn base_ignore_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(config_home) = config::config_home() {
+        files.push(config_home.join("git").join("ignore"));
+    }
+    files.push(root.join(".git").join("info").join("exclude"));
+    files
+}
+
+/// `prefix` is the slash-terminated repo path of `dir` ("" for the root)
+fn visit_dir(
+    dir: &Path,
+    prefix: &str,
+    file_states: &FileStates,
+    ignores: &Arc<GitIgnoreFile>,
+    new_files: &NewFiles,
+    count: &mut usize,
+) {
+    let ignores = ignores
+        .chain_with_file(prefix, dir.join(".gitignore"))
+        .unwrap_or_else(|_| ignores.clone());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if RESERVED_DIR_NAMES.contains(&name.as_str()) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = format!("{prefix}{name}");
+
+        if file_type.is_dir() {
+            let disk_dir = entry.path();
+            // Nested repos are skipped entirely, like jj does
+            let is_nested_repo = RESERVED_DIR_NAMES
+                .iter()
+                .any(|name| disk_dir.join(name).symlink_metadata().is_ok());
+            let dir_prefix = format!("{path}/");
+            if !is_nested_repo && !ignores.matches(&dir_prefix) {
+                visit_dir(&disk_dir, &dir_prefix, file_states, &ignores, new_files, count);
+            }
+        } else {
+            let Ok(repo_path) = RepoPath::from_internal_string(&path) else {
+                continue;
+            };
+            if !file_states.contains_path(repo_path)
+                && !ignores.matches(&path)
+                && new_files.tracks(repo_path, &entry.path())
+            {
+                *count += 1;
+            }
+        }
+    }
+}